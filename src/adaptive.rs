@@ -0,0 +1,324 @@
+use std::thread;
+
+use anyhow::{anyhow, Ok};
+use gstreamer::{
+    prelude::{Cast, ElementExt, GstBinExt, GstBinExtManual, ObjectExt, PluginFeatureExtManual},
+    Bin, ClockTime, Element, MessageView, Pipeline, Rank, Registry, State, URIType, ELEMENT_METADATA_KLASS,
+};
+use gstreamer_app::AppSink;
+use stereokit_rust::system::Log;
+
+use crate::status::PlaybackStatusHandle;
+
+/// The adaptivedemux2 elements (they only work inside decodebin3, playbin3 or urisourcebin) and the legacy ones
+pub const ADAPTIVE_DEMUXERS: [(&str, &str); 3] =
+    [("hlsdemux2", "hlsdemux"), ("dashdemux2", "dashdemux"), ("mssdemux2", "mssdemux")];
+
+/// The name of the element messages posted by the adaptive demuxers for each download
+pub const ADAPTIVE_STATISTICS: &str = "adaptive-streaming-statistics";
+
+/// A variant (HLS) or a video representation (DASH) of an adaptive stream
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Variant {
+    /// Media playlist of the variant (HLS) or id of the representation (DASH)
+    pub uri: String,
+    /// Peak bitrate in bits/s
+    pub bandwidth: u32,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub codecs: Option<String>,
+}
+
+impl Variant {
+    /// A readable description (ex: "1280x720 2.5Mb/s")
+    pub fn label(&self) -> String {
+        let mbps = self.bandwidth as f64 / 1_000_000.0;
+        match (self.width, self.height) {
+            (Some(width), Some(height)) => format!("{}x{} {:.1}Mb/s", width, height, mbps),
+            _ => format!("{:.1}Mb/s", mbps),
+        }
+    }
+}
+
+/// The variants of a HLS or DASH stream and the one being played
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AdaptiveStatus {
+    /// Sorted by bandwidth
+    pub variants: Vec<Variant>,
+    /// The variant being downloaded if known
+    pub current: Option<usize>,
+    /// The variant asked with select_variant(), None lets the demuxer follow the bandwidth
+    pub selected: Option<usize>,
+    /// Download bandwidth measured by the demuxer in bits/s
+    pub bandwidth: Option<u32>,
+}
+
+impl AdaptiveStatus {
+    /// The (min-bitrate, max-bitrate) of the demuxers keeping them on the selected variant, (0, 0) for the automatic
+    /// selection
+    pub fn bitrate_range(&self) -> (u32, u32) {
+        match self.selected.and_then(|index| self.variants.get(index)) {
+            Some(variant) => (variant.bandwidth, variant.bandwidth),
+            None => (0, 0),
+        }
+    }
+
+    /// The variant of a downloaded uri: a media playlist of a variant, or a fragment under the directory of only
+    /// one variant. Without a match the variant with the decoded `video_size` if only one has it.
+    pub fn variant_of(&self, uri: &str, video_size: Option<(u32, u32)>) -> Option<usize> {
+        if let Some(index) = self.variants.iter().position(|variant| variant.uri == uri) {
+            return Some(index);
+        }
+        let under_dir: Vec<usize> = (0..self.variants.len())
+            .filter(|index| {
+                let variant_uri = &self.variants[*index].uri;
+                let dir = &variant_uri[..variant_uri.rfind('/').map(|slash| slash + 1).unwrap_or(0)];
+                !dir.is_empty() && uri.starts_with(dir)
+            })
+            .collect();
+        let by_size: Vec<usize> = match video_size {
+            Some((width, height)) => (0..self.variants.len())
+                .filter(|index| {
+                    let variant = &self.variants[*index];
+                    variant.width == Some(width) && variant.height == Some(height)
+                })
+                .collect(),
+            None => vec![],
+        };
+        match (under_dir.as_slice(), by_size.as_slice()) {
+            ([index], _) | (_, [index]) => Some(*index),
+            _ => None,
+        }
+    }
+}
+
+/// Does this location look like a HLS playlist or a DASH manifest
+pub fn is_manifest(location: &str) -> bool {
+    let path = location.split(['?', '#']).next().unwrap_or(location).to_lowercase();
+    path.ends_with(".m3u8") || path.ends_with(".mpd")
+}
+
+/// Resolve a uri of a manifest relative to the uri of the manifest
+pub fn resolve_uri(base: &str, relative: &str) -> String {
+    if relative.contains("://") {
+        return relative.to_string();
+    }
+    if let Some(absolute_path) = relative.strip_prefix('/') {
+        // scheme://host/
+        let host_end = base.find("://").and_then(|scheme| base[scheme + 3..].find('/').map(|slash| scheme + 3 + slash));
+        return match host_end {
+            Some(host_end) => format!("{}/{}", &base[..host_end], absolute_path),
+            None => format!("{}/{}", base.trim_end_matches('/'), absolute_path),
+        };
+    }
+    let dir = &base[..base.rfind('/').map(|slash| slash + 1).unwrap_or(0)];
+    format!("{}{}", dir, relative)
+}
+
+/// The variants of a HLS master playlist or of a DASH manifest, sorted by bandwidth. A HLS media playlist has none.
+pub fn parse_manifest(content: &str, manifest_uri: &str) -> Vec<Variant> {
+    let mut variants = if content.trim_start().starts_with("#EXTM3U") {
+        parse_hls_master(content, manifest_uri)
+    } else {
+        parse_dash_manifest(content)
+    };
+    variants.sort_by_key(|variant| variant.bandwidth);
+    variants
+}
+
+/// The #EXT-X-STREAM-INF entries of a HLS master playlist
+pub fn parse_hls_master(content: &str, manifest_uri: &str) -> Vec<Variant> {
+    let mut variants = vec![];
+    let mut stream_inf: Option<Variant> = None;
+    for line in content.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            let mut variant = Variant::default();
+            for (name, value) in hls_attributes(attributes) {
+                match name {
+                    "BANDWIDTH" => variant.bandwidth = value.parse().unwrap_or(0),
+                    "RESOLUTION" => {
+                        let (width, height) = value.split_once('x').unwrap_or(("", ""));
+                        variant.width = width.parse().ok();
+                        variant.height = height.parse().ok();
+                    }
+                    "CODECS" => variant.codecs = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+            stream_inf = Some(variant);
+        } else if !line.starts_with('#') {
+            // the uri following its #EXT-X-STREAM-INF
+            if let Some(mut variant) = stream_inf.take() {
+                variant.uri = resolve_uri(manifest_uri, line);
+                variants.push(variant);
+            }
+        }
+    }
+    variants
+}
+
+/// NAME=value,NAME="quoted, value" attributes of a HLS tag
+fn hls_attributes(attributes: &str) -> Vec<(&str, &str)> {
+    let mut result = vec![];
+    let mut rest = attributes;
+    while let Some((name, value_and_rest)) = rest.split_once('=') {
+        let (value, next) = if let Some(quoted) = value_and_rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            (&quoted[..end], quoted[end..].trim_start_matches('"'))
+        } else {
+            let end = value_and_rest.find(',').unwrap_or(value_and_rest.len());
+            (&value_and_rest[..end], &value_and_rest[end..])
+        };
+        result.push((name.trim(), value));
+        rest = next.trim_start_matches(',');
+    }
+    result
+}
+
+/// The video Representations of a DASH manifest. Their uri is their id.
+pub fn parse_dash_manifest(content: &str) -> Vec<Variant> {
+    let mut variants = vec![];
+    // the mimeType or contentType of the current AdaptationSet
+    let mut set_type = String::new();
+    for tag in content.split('<').skip(1) {
+        let tag = tag.split('>').next().unwrap_or(tag);
+        let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+        let attribute = |key: &str| xml_attribute(attributes, key);
+        match name {
+            "AdaptationSet" => {
+                set_type = attribute("mimeType").or_else(|| attribute("contentType")).unwrap_or_default();
+            }
+            "/AdaptationSet" => set_type.clear(),
+            "Representation" => {
+                let media_type = attribute("mimeType").unwrap_or_else(|| set_type.clone());
+                let is_video = media_type.contains("video") || (media_type.is_empty() && attribute("width").is_some());
+                if !is_video {
+                    continue;
+                }
+                variants.push(Variant {
+                    uri: attribute("id").unwrap_or_default(),
+                    bandwidth: attribute("bandwidth").and_then(|bandwidth| bandwidth.parse().ok()).unwrap_or(0),
+                    width: attribute("width").and_then(|width| width.parse().ok()),
+                    height: attribute("height").and_then(|height| height.parse().ok()),
+                    codecs: attribute("codecs"),
+                });
+            }
+            _ => {}
+        }
+    }
+    variants
+}
+
+/// The value of key="value" in the attributes of a xml tag
+fn xml_attribute(attributes: &str, key: &str) -> Option<String> {
+    attributes.split_whitespace().find_map(|attribute| {
+        let (name, value) = attribute.split_once('=')?;
+        (name == key).then(|| value.trim_end_matches('/').trim_matches(|c| c == '"' || c == '\'').to_string())
+    })
+}
+
+/// Read the content of a uri (file://, http://, https://) with the GStreamer source handling it
+pub fn fetch_uri(uri: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let pipeline = Pipeline::default();
+    let source = Element::make_from_uri(URIType::Src, uri, None)?;
+    let appsink = AppSink::builder().sync(false).build();
+    pipeline.add_many([&source, appsink.upcast_ref()])?;
+    source.link(&appsink)?;
+    pipeline.set_state(State::Playing)?;
+
+    let mut content = vec![];
+    while let Some(sample) = appsink.try_pull_sample(ClockTime::from_seconds(10)) {
+        if let Some(buffer) = sample.buffer() {
+            content.extend_from_slice(buffer.map_readable()?.as_slice());
+        }
+    }
+    let error = pipeline.bus().and_then(|bus| bus.pop_filtered(&[gstreamer::MessageType::Error]));
+    pipeline.set_state(State::Null)?;
+    if let Some(msg) = error {
+        if let MessageView::Error(err) = msg.view() {
+            return Err(anyhow!("Unable to read {} : {}", uri, err.error()));
+        }
+    }
+    if !appsink.is_eos() {
+        return Err(anyhow!("Timeout when reading {}", uri));
+    }
+    Ok(content)
+}
+
+/// Read the variants of the manifest in a thread, the adaptive status is set when they are known
+pub fn load_variants(manifest_uri: String, status: PlaybackStatusHandle) {
+    thread::spawn(move || {
+        let variants = match fetch_uri(&manifest_uri) {
+            Result::Ok(content) => parse_manifest(&String::from_utf8_lossy(&content), &manifest_uri),
+            Err(err) => {
+                Log::warn(format!("Unable to read the variants of {} : {:?}", manifest_uri, err));
+                return;
+            }
+        };
+        for variant in &variants {
+            Log::diag(format!("Variant {} : {}", variant.label(), variant.uri));
+        }
+        let mut status = status.lock().unwrap();
+        let adaptive = status.adaptive.get_or_insert_with(AdaptiveStatus::default);
+        adaptive.variants = variants;
+    });
+}
+
+/// The adaptive demuxers of a pipeline
+pub fn adaptive_demuxers(pipeline: &Element) -> Vec<Element> {
+    let Some(bin) = pipeline.downcast_ref::<Bin>() else {
+        return vec![];
+    };
+    bin.iterate_recurse().into_iter().flatten().filter(is_adaptive_demuxer).collect()
+}
+
+fn is_adaptive_demuxer(element: &Element) -> bool {
+    element
+        .factory()
+        .and_then(|factory| factory.metadata(ELEMENT_METADATA_KLASS).map(|klass| klass.contains("Demuxer/Adaptive")))
+        .unwrap_or(false)
+}
+
+/// Keep the bitrate of the demuxer between min and max bits/s (0 for no limit)
+pub fn set_bitrate_range(demuxer: &Element, (min, max): (u32, u32)) {
+    for (property, bitrate) in [("min-bitrate", min), ("max-bitrate", max)] {
+        if demuxer.has_property(property, None) {
+            demuxer.set_property(property, bitrate);
+        }
+    }
+}
+
+/// The bandwidth measured by the demuxer in bits/s
+pub fn current_bandwidth(demuxer: &Element) -> Option<u32> {
+    demuxer
+        .has_property("current-bandwidth", None)
+        .then(|| demuxer.property::<u32>("current-bandwidth"))
+}
+
+/// Apply the bitrate range of the selected variant to the adaptive demuxers created later by decodebin3/playbin3
+pub fn connect_adaptive_demuxers(pipeline: &Element, status: PlaybackStatusHandle) {
+    let Some(bin) = pipeline.downcast_ref::<Bin>() else {
+        return;
+    };
+    bin.connect_deep_element_added(move |_, _, element| {
+        if is_adaptive_demuxer(element) {
+            if let Some(adaptive) = &status.lock().unwrap().adaptive {
+                set_bitrate_range(element, adaptive.bitrate_range());
+            }
+        }
+    });
+}
+
+/// Lower the rank of the legacy hlsdemux/dashdemux/mssdemux when their adaptivedemux2 version is installed so
+/// decodebin3 and playbin3 use the new ones
+pub fn prefer_adaptivedemux2() {
+    let registry = Registry::get();
+    for (name, legacy_name) in ADAPTIVE_DEMUXERS {
+        if registry.lookup_feature(name).is_none() {
+            continue;
+        }
+        if let Some(legacy) = registry.lookup_feature(legacy_name) {
+            legacy.set_rank(Rank::SECONDARY);
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use anyhow::{anyhow, Ok};
+use byte_slice_cast::AsSliceOf;
+use gstreamer::{
+    element_error,
+    prelude::{Cast, ElementExt, GstBinExt, GstObjectExt, ObjectExt},
+    Bin, Element, ElementFactory,
+};
+use gstreamer_app::{AppSink, AppSinkCallbacks};
+use gstreamer_audio::{AudioCapsBuilder, AudioInfo, AUDIO_FORMAT_F32};
+
+use crate::status::PlaybackStatusHandle;
+use stereokit_rust::{
+    maths::{Matrix, Vec2, Vec3},
+    sound::Sound,
+};
+
+/// StereoKit stream sounds are mono at this rate
+pub const SOUND_RATE: i32 = 48000;
+
+/// Prefix of the names of our volume and equalizer elements (playbin may have its own volume element)
+const VOLUME_PREFIX: &str = "sk_volume";
+const EQUALIZER_PREFIX: &str = "sk_equalizer";
+static EFFECT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+const EQUALIZER_3_BANDS: &str = "equalizer-3bands";
+const EQUALIZER_10_BANDS: &str = "equalizer-10bands";
+const VOLUME: &str = "volume";
+/// The factories audio_effects() may build, depending on the number of bands of the equalizer
+pub const EFFECT_FACTORIES: [&str; 3] = [EQUALIZER_3_BANDS, EQUALIZER_10_BANDS, VOLUME];
+
+/// Gain limits in dB of equalizer-3bands and equalizer-10bands
+pub const EQUALIZER_MIN_GAIN: f64 = -24.0;
+pub const EQUALIZER_MAX_GAIN: f64 = 12.0;
+
+/// The audio processing of a screen, applied to every audio stream of its pipeline
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioSettings {
+    /// 0.0 to 10.0 (1.0 is 100%)
+    pub volume: f64,
+    pub mute: bool,
+    /// Gains in dB of the equalizer. 3 or 10 bands (equalizer-3bands or equalizer-10bands), the number of bands is
+    /// used when the pipeline is created.
+    pub equalizer: Vec<f64>,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self { volume: 1.0, mute: false, equalizer: vec![0.0; 3] }
+    }
+}
+
+/// The audio settings shared between a stepper and its streaming threads
+pub type AudioSettingsHandle = Arc<Mutex<AudioSettings>>;
+
+impl AudioSettings {
+    /// Set the gain of a band. The gain is clamped to the equalizer limits.
+    pub fn set_band(&mut self, band: usize, gain: f64) -> Result<(), anyhow::Error> {
+        let bands = self.equalizer.len();
+        let value = self.equalizer.get_mut(band).ok_or(anyhow!("No band {} in a {} bands equalizer", band, bands))?;
+        *value = gain.clamp(EQUALIZER_MIN_GAIN, EQUALIZER_MAX_GAIN);
+        Ok(())
+    }
+
+    fn equalizer_factory(&self) -> &'static str {
+        if self.equalizer.len() == 10 {
+            EQUALIZER_10_BANDS
+        } else {
+            EQUALIZER_3_BANDS
+        }
+    }
+}
+
+/// Create the equalizer and the volume elements of an audio branch (to insert after an audioconvert)
+pub fn audio_effects(settings: &AudioSettings) -> Result<[Element; 2], anyhow::Error> {
+    let count = EFFECT_COUNT.fetch_add(1, Ordering::Relaxed);
+    let equalizer = ElementFactory::make(settings.equalizer_factory())
+        .name(format!("{}{}", EQUALIZER_PREFIX, count))
+        .build()?;
+    let volume = ElementFactory::make(VOLUME).name(format!("{}{}", VOLUME_PREFIX, count)).build()?;
+    apply_to_equalizer(&equalizer, settings);
+    apply_to_volume(&volume, settings);
+    Ok([equalizer, volume])
+}
+
+/// Update the volume and equalizer elements of a running pipeline
+pub fn apply_audio_settings(pipeline: &Element, settings: &AudioSettings) {
+    let Some(bin) = pipeline.downcast_ref::<Bin>() else {
+        return;
+    };
+    for element in bin.iterate_recurse().into_iter().flatten() {
+        let name = element.name();
+        if name.starts_with(VOLUME_PREFIX) {
+            apply_to_volume(&element, settings);
+        } else if name.starts_with(EQUALIZER_PREFIX) {
+            apply_to_equalizer(&element, settings);
+        }
+    }
+}
+
+fn apply_to_volume(volume: &Element, settings: &AudioSettings) {
+    volume.set_property("volume", settings.volume.clamp(0.0, 10.0));
+    volume.set_property("mute", settings.mute);
+}
+
+fn apply_to_equalizer(equalizer: &Element, settings: &AudioSettings) {
+    // the bands of a 10 bands equalizer can't be set on a 3 bands one created before a change of settings
+    let bands = match equalizer.factory() {
+        Some(factory) if factory.name() == EQUALIZER_10_BANDS => 10,
+        _ => 3,
+    };
+    for (band, gain) in settings.equalizer.iter().take(bands).enumerate() {
+        equalizer.set_property(&format!("band{}", band), gain.clamp(EQUALIZER_MIN_GAIN, EQUALIZER_MAX_GAIN));
+    }
+}
+
+/// A speaker of a layout. Its position is relative to the center of the screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Speaker {
+    pub name: &'static str,
+    /// to the right, in screen widths
+    pub right: f32,
+    /// below the center, in screen heights
+    pub down: f32,
+    /// toward the viewer, in meters
+    pub front: f32,
+}
+
+const MONO: [Speaker; 1] = [Speaker { name: "center", right: 0.0, down: 0.0, front: 0.0 }];
+
+const STEREO: [Speaker; 2] = [
+    Speaker { name: "left", right: -0.5, down: 0.0, front: 0.0 },
+    Speaker { name: "right", right: 0.5, down: 0.0, front: 0.0 },
+];
+
+/// Same order as the GStreamer 5.1 channel mask: FL, FR, FC, LFE, RL, RR
+const SURROUND_51: [Speaker; 6] = [
+    Speaker { name: "front_left", right: -0.5, down: 0.0, front: 0.0 },
+    Speaker { name: "front_right", right: 0.5, down: 0.0, front: 0.0 },
+    Speaker { name: "center", right: 0.0, down: 0.0, front: 0.0 },
+    Speaker { name: "lfe", right: 0.0, down: 0.5, front: 0.3 },
+    Speaker { name: "rear_left", right: -0.8, down: 0.0, front: 2.0 },
+    Speaker { name: "rear_right", right: 0.8, down: 0.0, front: 2.0 },
+];
+
+/// How the audio of a screen is spread into spatial sounds. Each channel is a mono StereoKit stream sound placed
+/// around the screen, audioconvert down/up mixes the media to this layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpeakerLayout {
+    /// Everything downmixed at the center of the screen
+    Mono,
+    /// Left and right edges of the screen
+    #[default]
+    Stereo,
+    /// 5.1 downmixed to 6 spatial sounds: front speakers on the screen, LFE below, rear speakers near the viewer
+    Surround51,
+}
+
+impl SpeakerLayout {
+    pub fn speakers(&self) -> &'static [Speaker] {
+        match self {
+            SpeakerLayout::Mono => &MONO,
+            SpeakerLayout::Stereo => &STEREO,
+            SpeakerLayout::Surround51 => &SURROUND_51,
+        }
+    }
+
+    pub fn channels(&self) -> i32 {
+        self.speakers().len() as i32
+    }
+
+    /// The GStreamer channel mask of the layout (0 for mono)
+    pub fn channel_mask(&self) -> u64 {
+        match self {
+            SpeakerLayout::Mono => 0,
+            SpeakerLayout::Stereo => 0x3,
+            SpeakerLayout::Surround51 => 0x3f,
+        }
+    }
+
+    /// World position of the speaker `index` for a screen of `size` (width, height) drawn with `transform_screen`
+    pub fn speaker_position(&self, index: usize, transform_screen: Matrix, size: Vec2) -> Vec3 {
+        let speaker = self.speakers()[index];
+        // the screen is a plane generated up (x right, z down) so y is toward the viewer
+        transform_screen.transform_point(Vec3::new(speaker.right * size.x, speaker.front, speaker.down * size.y))
+    }
+}
+
+/// The caps of the audio appsinks: interleaved f32 at the StereoKit rate with the channels of the layout
+pub fn audio_appsink_caps(layout: SpeakerLayout) -> gstreamer::Caps {
+    let builder = AudioCapsBuilder::new_interleaved()
+        .format(AUDIO_FORMAT_F32)
+        .rate(SOUND_RATE)
+        .channels(layout.channels());
+    if layout == SpeakerLayout::Mono {
+        builder.build()
+    } else {
+        builder.channel_mask(layout.channel_mask()).build()
+    }
+}
+
+/// Create one stream sound per speaker of the layout. The ids allow the streaming threads to find them back.
+pub fn create_sounds(stepper_id: &str, layout: SpeakerLayout) -> (Vec<Sound>, Vec<String>) {
+    let mut sounds = vec![];
+    let mut sound_ids = vec![];
+    for speaker in layout.speakers() {
+        let mut sound = Sound::create_stream(200.0).unwrap();
+        let sound_id = format!("{}sound_{}", stepper_id, speaker.name);
+        sound.id(&sound_id);
+        sounds.push(sound);
+        sound_ids.push(sound_id);
+    }
+    (sounds, sound_ids)
+}
+
+/// Find the sounds created by create_sounds()
+pub fn find_sounds(sound_ids: &[String]) -> Result<Vec<Sound>, anyhow::Error> {
+    let mut sounds = vec![];
+    for sound_id in sound_ids {
+        sounds.push(Sound::find(sound_id)?);
+    }
+    Ok(sounds)
+}
+
+/// Getting data out of the appsink is done by setting callbacks on it.
+/// The appsink will then call those handlers, as soon as data is available.
+/// The interleaved samples are split into the sounds, one per channel, and counted in the status. Samples with another
+/// number of channels than the sounds are refused (not negotiated).
+pub fn set_audio_callback(appsink: AppSink, mut sounds: Vec<Sound>, status: PlaybackStatusHandle) {
+    // the samples of each channel of an interleaved buffer, kept from one buffer to the next
+    let mut channel_samples: Vec<Vec<f32>> = vec![vec![]; sounds.len()];
+    appsink.set_callbacks(
+        AppSinkCallbacks::builder()
+            // Add a handler to the "new-sample" signal.
+            .new_sample(move |appsink| {
+                // Pull the sample in question out of the appsink's buffer.
+                let sample = appsink.pull_sample().map_err(|_| gstreamer::FlowError::Eos)?;
+                let channels = sample
+                    .caps()
+                    .and_then(|caps| AudioInfo::from_caps(caps).ok())
+                    .map(|info| info.channels() as usize)
+                    .unwrap_or_default();
+                if channels != sounds.len() {
+                    element_error!(
+                        appsink,
+                        gstreamer::CoreError::Negotiation,
+                        ("Audio samples with {} channels for {} sounds", channels, sounds.len())
+                    );
+                    return Err(gstreamer::FlowError::NotNegotiated);
+                }
+                let buffer = sample.buffer().ok_or_else(|| {
+                    element_error!(appsink, gstreamer::ResourceError::Failed, ("Failed to get buffer from appsink"));
+
+                    gstreamer::FlowError::Error
+                })?;
+
+                // At this point, buffer is only a reference to an existing memory region somewhere.
+                // When we want to access its content, we have to map it while requesting the required
+                // mode of access (read, read/write).
+                // See: https://gstreamer.freedesktop.org/documentation/plugin-development/advanced/allocation.html
+                let map = buffer.map_readable().map_err(|_| {
+                    element_error!(appsink, gstreamer::ResourceError::Failed, ("Failed to map Audio buffer readable"));
+
+                    gstreamer::FlowError::Error
+                })?;
+
+                // We know what format the data in the memory region has, since we requested
+                // it by setting the appsink's caps.
+                let samples = map.as_slice_of::<f32>().map_err(|_| {
+                    element_error!(appsink, gstreamer::ResourceError::Failed, ("Failed to interpret buffer as f32"));
+
+                    gstreamer::FlowError::Error
+                })?;
+
+                if channels == 1 {
+                    sounds[0].write_samples(samples.as_ptr(), samples.len() as u64);
+                } else {
+                    for (channel, (sound, mono)) in sounds.iter_mut().zip(channel_samples.iter_mut()).enumerate() {
+                        mono.clear();
+                        mono.extend(samples.iter().skip(channel).step_by(channels));
+                        sound.write_samples(mono.as_ptr(), mono.len() as u64);
+                    }
+                }
+                status.lock().unwrap().audio_samples += (samples.len() / channels.max(1)) as u64;
+                Result::<gstreamer::FlowSuccess, gstreamer::FlowError>::Ok(gstreamer::FlowSuccess::Ok)
+            })
+            .build(),
+    );
+}
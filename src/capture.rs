@@ -0,0 +1,78 @@
+use anyhow::{anyhow, Ok};
+use gstreamer::{
+    prelude::{ElementExt, GstBinExtManual, ObjectExt},
+    Bin, Element, ElementFactory, GhostPad,
+};
+
+/// What a ScreenCapture shows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureTarget {
+    /// A whole X display (ex: ":0", ":99" for a Xvfb), the one of $DISPLAY if None
+    Display(Option<String>),
+    /// A X window of the display of $DISPLAY by its id (see xwininfo)
+    Window(u64),
+    /// A rectangle of a X display
+    Region { display: Option<String>, x: u32, y: u32, width: u32, height: u32 },
+    /// A PipeWire node (Wayland: the node given by the screencast portal of the compositor), the default one if None.
+    /// The compositor decides if the cursor is drawn.
+    PipeWire(Option<String>),
+}
+
+impl Default for CaptureTarget {
+    fn default() -> Self {
+        CaptureTarget::Display(None)
+    }
+}
+
+/// The source element of the target: ximagesrc or pipewiresrc
+pub fn capture_source(target: &CaptureTarget, show_cursor: bool) -> Result<Element, anyhow::Error> {
+    if let CaptureTarget::PipeWire(node) = target {
+        let pipewiresrc = ElementFactory::make("pipewiresrc").property("do-timestamp", true).build()?;
+        if let Some(node) = node {
+            // target-object replaced path in PipeWire 0.3.64
+            let property = if pipewiresrc.has_property("target-object", None) { "target-object" } else { "path" };
+            pipewiresrc.set_property(property, node);
+        }
+        return Ok(pipewiresrc);
+    }
+
+    // damage events would only send the modified parts of the screen
+    let ximagesrc = ElementFactory::make("ximagesrc")
+        .property("show-pointer", show_cursor)
+        .property("use-damage", false)
+        .build()?;
+    match target {
+        CaptureTarget::Display(Some(display)) => ximagesrc.set_property("display-name", display),
+        CaptureTarget::Window(xid) => ximagesrc.set_property("xid", *xid),
+        CaptureTarget::Region { display, x, y, width, height } => {
+            if *width == 0 || *height == 0 {
+                return Err(anyhow!("Empty capture region {}x{}", width, height));
+            }
+            if let Some(display) = display {
+                ximagesrc.set_property("display-name", display);
+            }
+            // the end is inclusive
+            ximagesrc.set_property("startx", *x);
+            ximagesrc.set_property("starty", *y);
+            ximagesrc.set_property("endx", x + width - 1);
+            ximagesrc.set_property("endy", y + height - 1);
+        }
+        _ => {}
+    }
+    Ok(ximagesrc)
+}
+
+/// A bin `source ! videorate` with a src pad. The videorate drops the frames above `max_fps` (no limit if 0).
+pub fn screen_capture_bin(target: &CaptureTarget, show_cursor: bool, max_fps: i32) -> Result<Bin, anyhow::Error> {
+    let bin = Bin::new();
+    let source = capture_source(target, show_cursor)?;
+    let videorate = ElementFactory::make("videorate").property("drop-only", true).build()?;
+    if max_fps > 0 {
+        videorate.set_property("max-rate", max_fps);
+    }
+    bin.add_many([&source, &videorate])?;
+    source.link(&videorate)?;
+    let src_pad = videorate.static_pad("src").expect("videorate has no src pad");
+    bin.add_pad(&GhostPad::with_target(&src_pad)?)?;
+    Ok(bin)
+}
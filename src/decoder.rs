@@ -0,0 +1,174 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Ok};
+use gstreamer::{
+    glib::{self, translate::IntoGlib, EnumClass, SignalId},
+    prelude::{ElementExt, GstObjectExt, ObjectExt, PluginFeatureExtManual},
+    Caps, CoreError, Element, ElementFactory, ElementFactoryType, Rank, State, StreamError,
+};
+
+/// GstAutoplugSelectResult values of the autoplug-select signal of decodebin
+const AUTOPLUG_SELECT_TRY: i32 = 0;
+const AUTOPLUG_SELECT_SKIP: i32 = 2;
+
+/// A decoder of the registry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecoderCandidate {
+    /// The factory name (ex: avdec_h264)
+    pub name: String,
+    pub rank: i32,
+    /// The klass of the factory says Hardware (vaapi, va, nvcodec, v4l2, androidmedia, ...)
+    pub hardware: bool,
+}
+
+impl DecoderCandidate {
+    fn from_factory(factory: &ElementFactory) -> Self {
+        Self {
+            name: factory.name().to_string(),
+            rank: factory.rank().into_glib(),
+            hardware: factory.has_type(ElementFactoryType::HARDWARE),
+        }
+    }
+}
+
+/// Chooses the decoder of a stream among the decoders of the registry. A candidate that is missing, refuses the caps
+/// or fails to open its device is skipped for the next one. The clones share the list of the decoders plugged in the
+/// current pipeline (see plugged()).
+#[derive(Debug, Clone)]
+pub struct DecoderResolver {
+    /// Factory names tried first, in this order (ex: "vah264dec", "avdec_h264")
+    pub preferred: Vec<String>,
+    /// Factory names never used (a decoder that failed is added here by the player)
+    pub excluded: Vec<String>,
+    /// Try the hardware decoders before the software ones whatever their ranks
+    pub hardware_first: bool,
+    plugged: Arc<Mutex<Vec<String>>>,
+}
+
+impl Default for DecoderResolver {
+    fn default() -> Self {
+        Self { preferred: vec![], excluded: vec![], hardware_first: true, plugged: Arc::new(Mutex::new(vec![])) }
+    }
+}
+
+/// Is the error a failed negotiation of the caps. It is posted by the source or the demuxer upstream of the decoder
+/// (internal data stream error, reason not-negotiated).
+pub fn is_negotiation_error(error: &glib::Error, debug: Option<&str>) -> bool {
+    error.matches(CoreError::Negotiation)
+        || (error.matches(StreamError::Failed) && debug.is_some_and(|debug| debug.contains("not-negotiated")))
+}
+
+impl DecoderResolver {
+    /// The decoders accepting `caps` in the order they are tried: the preferred ones, then the hardware ones (if
+    /// hardware_first) and the others by rank. The factories of rank NONE are only used if they are preferred.
+    pub fn candidates(&self, caps: &Caps) -> Vec<DecoderCandidate> {
+        let factories = ElementFactory::factories_with_type(ElementFactoryType::DECODER, Rank::NONE);
+        let mut decoders: Vec<DecoderCandidate> = factories
+            .iter()
+            .filter(|factory| factory.can_sink_any_caps(caps))
+            .map(DecoderCandidate::from_factory)
+            .filter(|candidate| !self.excluded.contains(&candidate.name))
+            .filter(|candidate| candidate.rank > Rank::NONE.into_glib() || self.preferred.contains(&candidate.name))
+            .collect();
+        let preference = |candidate: &DecoderCandidate| {
+            self.preferred.iter().position(|name| *name == candidate.name).unwrap_or(self.preferred.len())
+        };
+        decoders.sort_by(|a, b| {
+            preference(a)
+                .cmp(&preference(b))
+                .then((self.hardware_first && b.hardware).cmp(&(self.hardware_first && a.hardware)))
+                .then(b.rank.cmp(&a.rank))
+                .then(a.name.cmp(&b.name))
+        });
+        decoders
+    }
+
+    /// Build the first candidate for `caps` that reaches the READY state (a hardware decoder without its device fails
+    /// there). The error lists the candidates that failed.
+    pub fn resolve(&self, caps: &Caps) -> Result<(Element, DecoderCandidate), anyhow::Error> {
+        let mut failures = vec![];
+        for candidate in self.candidates(caps) {
+            let decoder = match ElementFactory::make(&candidate.name).build() {
+                Result::Ok(decoder) => decoder,
+                Err(err) => {
+                    failures.push(format!("{} ({})", candidate.name, err));
+                    continue;
+                }
+            };
+            let ready = decoder.set_state(State::Ready);
+            let _ = decoder.set_state(State::Null);
+            match ready {
+                Result::Ok(_) => {
+                    self.plugged.lock().unwrap().push(candidate.name.clone());
+                    return Ok((decoder, candidate));
+                }
+                Err(err) => failures.push(format!("{} ({})", candidate.name, err)),
+            }
+        }
+        if failures.is_empty() {
+            Err(anyhow!("No decoder for {}", caps))
+        } else {
+            Err(anyhow!("No decoder for {}, failed : {}", caps, failures.join(", ")))
+        }
+    }
+
+    /// resolve() for the caps of a media type (ex: video/x-vp8)
+    pub fn resolve_media_type(&self, media_type: &str) -> Result<(Element, DecoderCandidate), anyhow::Error> {
+        self.resolve(&Caps::new_empty_simple(media_type))
+    }
+
+    /// Let a decodebin (or uridecodebin) plug the decoders the resolver would choose: the excluded ones are skipped and
+    /// the plugged ones are recorded. decodebin3 has no autoplug-select signal, its decoders are not watched.
+    pub fn watch_decodebin(&self, decodebin: &Element) {
+        if SignalId::lookup("autoplug-select", decodebin.type_()).is_none() {
+            return;
+        }
+        let excluded = self.excluded.clone();
+        decodebin.connect("autoplug-select", false, move |args| {
+            let factory = args[3].get::<ElementFactory>().ok()?;
+            let skip = excluded.iter().any(|name| name == factory.name().as_str());
+            let class = EnumClass::with_type(glib::Type::from_name("GstAutoplugSelectResult")?)?;
+            class.to_value(if skip { AUTOPLUG_SELECT_SKIP } else { AUTOPLUG_SELECT_TRY })
+        });
+        let plugged = self.plugged.clone();
+        decodebin.connect("deep-element-added", false, move |args| {
+            let element = args[2].get::<Element>().ok()?;
+            if element.factory().is_some_and(|factory| factory.has_type(ElementFactoryType::DECODER)) {
+                let factory = element.factory()?.name().to_string();
+                plugged.lock().unwrap().push(factory);
+            }
+            None
+        });
+    }
+
+    /// The decoders built by resolve() or plugged by a watched decodebin since clear_plugged(), in this order
+    pub fn plugged(&self) -> Vec<String> {
+        self.plugged.lock().unwrap().clone()
+    }
+
+    /// Forget the decoders of the previous pipeline
+    pub fn clear_plugged(&self) {
+        self.plugged.lock().unwrap().clear();
+    }
+
+    /// The plugged decoder that caused an error: the element `factory` that posted it if it is a plugged decoder or,
+    /// for a failed negotiation posted upstream, the last plugged decoder
+    pub fn failed_decoder(&self, factory: Option<&str>, negotiation: bool) -> Option<String> {
+        let plugged = self.plugged.lock().unwrap();
+        match factory {
+            Some(factory) if plugged.iter().any(|name| name == factory) => Some(factory.to_string()),
+            _ if negotiation => plugged.last().cloned(),
+            _ => None,
+        }
+    }
+
+    /// Exclude the decoder `factory` after an error. false if it is not a decoder or if it is already excluded.
+    pub fn exclude(&mut self, factory: &str) -> bool {
+        let is_decoder = ElementFactory::find(factory).is_some_and(|f| f.has_type(ElementFactoryType::DECODER));
+        if !is_decoder || self.excluded.iter().any(|name| name == factory) {
+            return false;
+        }
+        self.excluded.push(factory.to_string());
+        true
+    }
+}
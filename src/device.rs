@@ -0,0 +1,156 @@
+use anyhow::{anyhow, Ok};
+use gstreamer::{
+    glib::{value::FromValue, BoolError},
+    prelude::{DeviceExt, DeviceMonitorExt, DeviceMonitorExtManual, ElementExt, GstBinExtManual},
+    Bin, Caps, Device, DeviceMonitor, Element, ElementFactory, Fraction, GhostPad, List, StructureRef,
+};
+
+/// Device class of the cameras and capture cards
+pub const VIDEO_SOURCE_CLASS: &str = "Video/Source";
+/// Device class of the microphones and audio inputs
+pub const AUDIO_SOURCE_CLASS: &str = "Audio/Source";
+
+/// A fixed set of caps a camera can deliver
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceMode {
+    /// video/x-raw, image/jpeg, video/x-h264, ...
+    pub media_type: String,
+    /// The pixel format of video/x-raw (YUY2, NV12, ...)
+    pub format: Option<String>,
+    pub width: i32,
+    pub height: i32,
+    /// numerator, denominator
+    pub framerate: (i32, i32),
+}
+
+impl DeviceMode {
+    /// The caps of the capsfilter following the device
+    pub fn caps(&self) -> Caps {
+        let mut builder = Caps::builder(self.media_type.as_str())
+            .field("width", self.width)
+            .field("height", self.height)
+            .field("framerate", Fraction::new(self.framerate.0, self.framerate.1));
+        if let Some(format) = &self.format {
+            builder = builder.field("format", format.as_str());
+        }
+        builder.build()
+    }
+
+    /// Ex: "1280x720 30fps YUY2", "1920x1080 30fps image/jpeg"
+    pub fn label(&self) -> String {
+        let fps = self.framerate.0 as f64 / self.framerate.1.max(1) as f64;
+        format!(
+            "{}x{} {}fps {}",
+            self.width,
+            self.height,
+            (fps * 100.0).round() / 100.0,
+            self.format.as_deref().unwrap_or(&self.media_type)
+        )
+    }
+}
+
+/// The fixed values of a field, a list gives all its values. Ranges are ignored.
+fn values<T: for<'a> FromValue<'a>>(structure: &StructureRef, field: &str) -> Vec<T> {
+    let Result::Ok(value) = structure.value(field) else {
+        return vec![];
+    };
+    if let Result::Ok(list) = value.get::<List>() {
+        return list.iter().filter_map(|value| value.get::<T>().ok()).collect();
+    }
+    value.get::<T>().into_iter().collect()
+}
+
+/// The modes of the caps of a device, the largest and fastest first
+pub fn device_modes(caps: &Caps) -> Vec<DeviceMode> {
+    let mut modes = vec![];
+    for structure in caps.iter() {
+        let formats = values::<String>(structure, "format");
+        let formats = if formats.is_empty() { vec![None] } else { formats.into_iter().map(Some).collect() };
+        for format in &formats {
+            for width in values::<i32>(structure, "width") {
+                for height in values::<i32>(structure, "height") {
+                    for framerate in values::<Fraction>(structure, "framerate") {
+                        let mode = DeviceMode {
+                            media_type: structure.name().to_string(),
+                            format: format.clone(),
+                            width,
+                            height,
+                            framerate: (framerate.numer(), framerate.denom()),
+                        };
+                        if !modes.contains(&mode) {
+                            modes.push(mode);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    modes.sort_by(|a, b| {
+        let fps = |mode: &DeviceMode| mode.framerate.0 as f64 / mode.framerate.1.max(1) as f64;
+        (b.width * b.height).cmp(&(a.width * a.height)).then(fps(b).total_cmp(&fps(a)))
+    });
+    modes
+}
+
+/// A camera or a microphone found by the device monitor
+#[derive(Debug, Clone)]
+pub struct CaptureDevice {
+    pub name: String,
+    /// VIDEO_SOURCE_CLASS or AUDIO_SOURCE_CLASS (possibly with more details)
+    pub class: String,
+    /// The modes of a camera (empty if its caps are only ranges)
+    pub modes: Vec<DeviceMode>,
+    device: Device,
+}
+
+impl CaptureDevice {
+    pub fn is_video(&self) -> bool {
+        self.class.starts_with(VIDEO_SOURCE_CLASS)
+    }
+
+    /// A new source element reading this device
+    pub fn create_element(&self) -> Result<Element, BoolError> {
+        self.device.create_element(None)
+    }
+}
+
+/// Enumerate the cameras and the microphones
+pub fn list_devices() -> Result<Vec<CaptureDevice>, anyhow::Error> {
+    let monitor = DeviceMonitor::new();
+    monitor.add_filter(Some(VIDEO_SOURCE_CLASS), None);
+    monitor.add_filter(Some(AUDIO_SOURCE_CLASS), None);
+    monitor.start()?;
+    let devices = monitor
+        .devices()
+        .into_iter()
+        .map(|device| CaptureDevice {
+            name: device.display_name().to_string(),
+            class: device.device_class().to_string(),
+            modes: device.caps().map(|caps| device_modes(&caps)).unwrap_or_default(),
+            device,
+        })
+        .collect();
+    monitor.stop();
+    Ok(devices)
+}
+
+/// The camera (`video`) or the microphone with this name
+pub fn find_device(name: &str, video: bool) -> Result<CaptureDevice, anyhow::Error> {
+    list_devices()?
+        .into_iter()
+        .find(|device| device.name == name && device.is_video() == video)
+        .ok_or_else(|| anyhow!("No {} named {}", if video { "camera" } else { "microphone" }, name))
+}
+
+/// A bin `source ! capsfilter` with a src pad. The capsfilter keeps the mode of the camera, or lets the device choose.
+pub fn capture_bin(source: &Element, mode: Option<&DeviceMode>) -> Result<Bin, anyhow::Error> {
+    let bin = Bin::new();
+    let capsfilter = ElementFactory::make("capsfilter")
+        .property("caps", mode.map(DeviceMode::caps).unwrap_or_else(Caps::new_any))
+        .build()?;
+    bin.add_many([source, &capsfilter])?;
+    source.link(&capsfilter)?;
+    let src_pad = capsfilter.static_pad("src").expect("capsfilter has no src pad");
+    bin.add_pad(&GhostPad::with_target(&src_pad)?)?;
+    Ok(bin)
+}
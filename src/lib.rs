@@ -1,7 +1,34 @@
+pub mod adaptive;
+pub mod audio;
+pub mod capture;
+pub mod decoder;
+pub mod device;
+pub mod pattern;
+pub mod playback;
+pub mod player;
+pub mod playlist;
+pub mod probe;
+pub mod rtp;
+pub mod rtsp;
+pub mod srt;
+pub mod status;
+pub mod subtitles;
+pub mod tracks;
+pub mod transport;
 pub mod video1;
 pub mod video2;
+pub mod whep;
 
-use std::sync::Mutex;
+use adaptive::prefer_adaptivedemux2;
+use audio::AudioSettingsHandle;
+use capture::CaptureTarget;
+use decoder::DecoderResolver;
+use device::{list_devices, CaptureDevice};
+use playlist::parse_m3u_file;
+use probe::{apply_rank_overrides, gstreamer_init, parse_rank_overrides, source_requirements, CapabilityReport};
+use rtsp::RtspTransport;
+use status::PlaybackStatusHandle;
+use std::{collections::HashMap, sync::Mutex};
 use stereokit_rust::{
     event_loop::{SkClosures, StepperAction},
     maths::{units::*, Matrix, Pose, Quat, Vec2, Vec3},
@@ -20,10 +47,60 @@ use stereokit_rust::{
         Color128, Gradient,
     },
 };
-use video1::{gstreamer_init, Video1, VideoType};
+use tracks::TrackKind;
+use transport::PlayerCommand;
+use video1::{Video1, VideoType};
 use video2::Video2;
 use winit::event_loop::EventLoop;
 
+/// Played by the "Launch" toggle when there is no videos/launch.txt
+const DEFAULT_LAUNCH: &str = "videotestsrc pattern=ball is-live=true ! video/x-raw,width=640,height=360 ! \
+    queue ! appsink name=videosink audiotestsrc wave=ticks is-live=true ! queue ! appsink name=audiosink";
+
+/// Played by the "RTSP" toggle when there is no videos/rtsp.txt (the default url of gst-rtsp-server test-launch)
+const DEFAULT_RTSP_URL: &str = "rtsp://127.0.0.1:8554/test";
+
+/// Played by the "HLS/DASH" toggle when there is no videos/adaptive.txt
+const DEFAULT_ADAPTIVE_URI: &str = "https://test-streams.mux.dev/x36xhzz/x36xhzz.m3u8";
+
+/// Played by the "SRT" toggle when there is no videos/srt.txt
+const DEFAULT_SRT_URI: &str = "srt://127.0.0.1:7001";
+
+/// Played by the "WHEP" toggle when there is no videos/whep.txt (the WHEP endpoint of a MediaMTX path named test)
+const DEFAULT_WHEP_URL: &str = "http://127.0.0.1:8889/test/whep";
+
+/// Ranks of elements given at startup, lines `<factory> <rank>` (ex: `vah264dec primary+1` or `avdec_h264 0`)
+const RANK_FILE: &str = "videos/ranks.txt";
+
+/// The trimmed content of a text file of the external path, None if there is no such file or if it is empty
+fn external_text(sk: &Sk, name: &str) -> Option<String> {
+    let file_path = get_external_path(sk.get_sk_info_clone())?.join(name);
+    let text = std::fs::read_to_string(file_path).ok()?;
+    Some(text.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+/// The "Camera" screen: a camera with one of its modes (or its own) and an optional microphone of `devices`
+fn device_video(devices: &[CaptureDevice], camera: Option<usize>, mode: Option<usize>, mic: Option<usize>) -> Video1 {
+    let camera = camera.and_then(|index| devices.get(index));
+    let mut video = Video1::new(VideoType::Device {
+        camera: camera.map(|device| device.name.clone()),
+        mode: camera.zip(mode).and_then(|(camera, mode)| camera.modes.get(mode).cloned()),
+        microphone: mic.and_then(|index| devices.get(index)).map(|device| device.name.clone()),
+    });
+    video.auto_size = true;
+    video.transform_screen = Matrix::tr(&(Vec3::new(11.5, 2.0, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+    video
+}
+
+/// The "Test pattern" screen: SMPTE bars and a sine tone, launched at start in test mode
+fn test_pattern_video() -> Video1 {
+    let mut video = Video1::new(VideoType::TestPattern { pattern: "smpte".into(), tone: Some("sine".into()) });
+    video.width = 640;
+    video.height = 360;
+    video.transform_screen = Matrix::tr(&(Vec3::new(15.5, 2.0, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+    video
+}
+
 /// Somewhere to copy the log
 static LOG_LOG: Mutex<Vec<LogItem>> = Mutex::new(vec![]);
 
@@ -79,7 +156,7 @@ pub fn _main(sk: Sk, event_loop: EventLoop<StepperAction>) {
     Sk::shutdown();
 }
 
-pub fn launch(mut sk: Sk, event_loop: EventLoop<StepperAction>, _is_testing: bool) {
+pub fn launch(mut sk: Sk, event_loop: EventLoop<StepperAction>, is_testing: bool) {
     Log::diag(
         "======================================================================================================== !!",
     );
@@ -154,6 +231,22 @@ pub fn launch(mut sk: Sk, event_loop: EventLoop<StepperAction>, _is_testing: boo
     if let Err(err) = gstreamer_init() {
         Log::err(format!("Error during gstreamer initialisation : {:?}", err));
     }
+    prefer_adaptivedemux2();
+    if let Some(text) = external_text(&sk, RANK_FILE) {
+        match parse_rank_overrides(&text) {
+            Ok(overrides) => {
+                for factory in apply_rank_overrides(&overrides) {
+                    Log::warn(format!("{} : no element {}", RANK_FILE, factory));
+                }
+            }
+            Err(err) => Log::warn(format!("Invalid {} : {:?}", RANK_FILE, err)),
+        }
+    }
+    let capabilities = CapabilityReport::probe(&DecoderResolver::default(), &source_requirements());
+    for line in capabilities.lines() {
+        Log::info(line);
+    }
+    let mut show_capabilities = false;
     Log::diag(
         "======================================================================================================== !!",
     );
@@ -161,14 +254,60 @@ pub fn launch(mut sk: Sk, event_loop: EventLoop<StepperAction>, _is_testing: boo
     let radio_off = Sprite::radio_off();
 
     let mut rtp_stream1 = false;
-    //let mut rtp_stream2 = false;
+    let mut rtp_stream2 = false;
+    let mut launch_active = false;
+    let mut rtsp_active = false;
+    let mut srt_active = false;
+    let mut whep_active = false;
+    let mut device_active = false;
+    let mut desktop_active = false;
+    let mut test_pattern_active = false;
+    let mut devices: Vec<CaptureDevice> = vec![];
+    let mut camera: Option<usize> = None;
+    let mut camera_mode: Option<usize> = None;
+    let mut microphone: Option<usize> = None;
     let mut v3_enabled = false;
     let mut video_h264_dec_active = false;
     let mut video_vp8_dec_active = false;
     let mut video_vp8_https_dec_active = false;
+    let mut adaptive_active = false;
     let mut playbin_h264_active = false;
+    let mut playlist_active = false;
     let mut video_h264_active = false;
     let mut video_mkv_vp8_active = false;
+    let mut video_mkv_vp9_active = false;
+    // the screen driven by the transport buttons
+    let screens = [
+        "PlaybinH264",
+        "PlaybinPlaylist",
+        "VideoH264",
+        "Videomkv_vp8",
+        "Videomkv_vp9",
+        "VideoH264_dec",
+        "Videovp8_dec",
+        "video_VP8_dec",
+        "RtpStream1",
+        "RtpRawStream",
+        "Launch",
+        "Rtsp",
+        "Srt",
+        "Whep",
+        "Device",
+        "Desktop",
+        "TestPattern",
+        "Adaptive",
+    ];
+    let mut controlled_screen = 0;
+    let mut statuses: HashMap<&str, PlaybackStatusHandle> = HashMap::new();
+    let mut audios: HashMap<&str, AudioSettingsHandle> = HashMap::new();
+    // the test mode (--test, --headless) goes through the texture and sound path with no asset
+    if is_testing {
+        let video_test_pattern = test_pattern_video();
+        statuses.insert("TestPattern", video_test_pattern.status_handle());
+        audios.insert("TestPattern", video_test_pattern.playback.audio_handle());
+        sk.push_action(StepperAction::add("TestPattern", video_test_pattern));
+        test_pattern_active = true;
+    }
     SkClosures::run_app(
         sk,
         event_loop,
@@ -194,36 +333,191 @@ pub fn launch(mut sk: Sk, event_loop: EventLoop<StepperAction>, _is_testing: boo
             if let Some(new_value) = Ui::toggle("Decodebin3", v3_enabled, None) {
                 v3_enabled = new_value;
             }
+            Ui::same_line();
+            if let Some(new_value) = Ui::toggle("Capabilities", show_capabilities, None) {
+                show_capabilities = new_value;
+            }
             Ui::next_line();
+            if show_capabilities {
+                // the decoders of each codec and the screens that can't be launched
+                Ui::label(format!("{} plugins", capabilities.plugins.len()), None, true);
+                for codec in &capabilities.codecs {
+                    Ui::label(codec.label(), None, true);
+                }
+                for source in &capabilities.sources {
+                    Ui::label(source.label(), None, true);
+                }
+            }
             Ui::hseparator();
 
             if let Some(new_value) = Ui::toggle("RtpStream", rtp_stream1, None) {
                 if new_value {
                     // launch rtp stream
-                    let mut rtp_stream = Video1::new(VideoType::RtpStream { port: 5000 });
-                    rtp_stream.width = 2288;
-                    rtp_stream.height = 1430;
+                    // the sender may be described by videos/rtp.sdp
+                    let sdp_file = get_external_path(sk.get_sk_info_clone())
+                        .map(|dir_path| dir_path.join("videos/rtp.sdp"))
+                        .filter(|file_path| file_path.exists());
+                    let mut rtp_stream = match sdp_file.map(Video1::from_sdp_file) {
+                        Some(Ok(rtp_stream)) => rtp_stream,
+                        sdp => {
+                            if let Some(Err(err)) = sdp {
+                                Log::warn(format!("Invalid videos/rtp.sdp : {:?}", err));
+                            }
+                            let mut rtp_stream = Video1::new(VideoType::RtpStream { port: 5000 });
+                            rtp_stream.width = 2288;
+                            rtp_stream.height = 1430;
+                            rtp_stream
+                        }
+                    };
                     rtp_stream.transform_screen =
                         Matrix::tr(&(Vec3::new(-0.5, 2.0, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    statuses.insert("RtpStream1", rtp_stream.status_handle());
+                    audios.insert("RtpStream1", rtp_stream.playback.audio_handle());
                     sk.push_action(StepperAction::add("RtpStream1", rtp_stream));
                 } else {
                     sk.push_action(StepperAction::Remove("RtpStream1".into()));
                 }
                 rtp_stream1 = new_value;
             }
-            // Ui::same_line();
-            // if let Some(new_value) = Ui::toggle("RtpRawStream", rtp_stream2, None) {
-            //     if new_value {
-            //         // launch rtp stream
-            //         let mut rtp_raw_stream = Video1::new(VideoType::RtpRawStream { port: 5000 });
-            //         rtp_raw_stream.transform_screen =
-            //             Matrix::tr(&(Vec3::new(1.5, 2.0, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
-            //         sk.push_action(StepperAction::add("RtpRawStream", rtp_raw_stream));
-            //     } else {
-            //         sk.push_action(StepperAction::Remove("RtpRawStream".into()));
-            //     }
-            //     rtp_stream2 = new_value;
-            // }
+            Ui::same_line();
+            if let Some(new_value) = Ui::toggle("RtpRawStream", rtp_stream2, None) {
+                if new_value {
+                    // launch rtp raw stream
+                    let mut rtp_raw_stream = Video1::new(VideoType::RtpRawStream { port: 5002 });
+                    rtp_raw_stream.width = 1280;
+                    rtp_raw_stream.height = 720;
+                    rtp_raw_stream.transform_screen =
+                        Matrix::tr(&(Vec3::new(1.5, 2.0, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    statuses.insert("RtpRawStream", rtp_raw_stream.status_handle());
+                    audios.insert("RtpRawStream", rtp_raw_stream.playback.audio_handle());
+                    sk.push_action(StepperAction::add("RtpRawStream", rtp_raw_stream));
+                } else {
+                    sk.push_action(StepperAction::Remove("RtpRawStream".into()));
+                }
+                rtp_stream2 = new_value;
+            }
+            Ui::same_line();
+            if let Some(new_value) = Ui::toggle("Launch", launch_active, None) {
+                if new_value {
+                    let description = external_text(sk, "videos/launch.txt").unwrap_or_else(|| DEFAULT_LAUNCH.into());
+                    Log::diag(format!("Launch : {}", description));
+                    // launch the gst-launch description
+                    let mut video_launch = Video1::new(VideoType::Launch { description });
+                    video_launch.auto_size = true;
+                    video_launch.transform_screen =
+                        Matrix::tr(&(Vec3::new(3.5, 2.0, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    statuses.insert("Launch", video_launch.status_handle());
+                    audios.insert("Launch", video_launch.playback.audio_handle());
+                    sk.push_action(StepperAction::add("Launch", video_launch));
+                } else {
+                    sk.push_action(StepperAction::Remove("Launch".into()));
+                }
+                launch_active = new_value;
+            }
+            Ui::same_line();
+            if let Some(new_value) = Ui::toggle("RTSP", rtsp_active, None) {
+                if new_value {
+                    let url = external_text(sk, "videos/rtsp.txt").unwrap_or_else(|| DEFAULT_RTSP_URL.into());
+                    // launch the rtsp client, reconnected if the server is lost
+                    let mut video_rtsp =
+                        Video1::new(VideoType::Rtsp { url, latency: 200, transport: RtspTransport::Auto });
+                    video_rtsp.auto_size = true;
+                    video_rtsp.transform_screen =
+                        Matrix::tr(&(Vec3::new(5.5, 2.0, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    statuses.insert("Rtsp", video_rtsp.status_handle());
+                    audios.insert("Rtsp", video_rtsp.playback.audio_handle());
+                    sk.push_action(StepperAction::add("Rtsp", video_rtsp));
+                } else {
+                    sk.push_action(StepperAction::Remove("Rtsp".into()));
+                }
+                rtsp_active = new_value;
+            }
+            Ui::same_line();
+            if let Some(new_value) = Ui::toggle("SRT", srt_active, None) {
+                if new_value {
+                    let uri = external_text(sk, "videos/srt.txt").unwrap_or_else(|| DEFAULT_SRT_URI.into());
+                    // launch the srt caller, reconnected if the sender is lost
+                    let mut video_srt = Video1::new(VideoType::Srt { uri, latency: 125, passphrase: None });
+                    video_srt.auto_size = true;
+                    video_srt.transform_screen =
+                        Matrix::tr(&(Vec3::new(7.5, 2.0, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    statuses.insert("Srt", video_srt.status_handle());
+                    audios.insert("Srt", video_srt.playback.audio_handle());
+                    sk.push_action(StepperAction::add("Srt", video_srt));
+                } else {
+                    sk.push_action(StepperAction::Remove("Srt".into()));
+                }
+                srt_active = new_value;
+            }
+            Ui::same_line();
+            if let Some(new_value) = Ui::toggle("WHEP", whep_active, None) {
+                if new_value {
+                    let url = external_text(sk, "videos/whep.txt").unwrap_or_else(|| DEFAULT_WHEP_URL.into());
+                    // negotiate with the WHEP endpoint, a new session is negotiated if the connection fails
+                    let mut video_whep = Video1::new(VideoType::Whep { url, token: None });
+                    video_whep.auto_size = true;
+                    video_whep.transform_screen =
+                        Matrix::tr(&(Vec3::new(9.5, 2.0, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    statuses.insert("Whep", video_whep.status_handle());
+                    audios.insert("Whep", video_whep.playback.audio_handle());
+                    sk.push_action(StepperAction::add("Whep", video_whep));
+                } else {
+                    sk.push_action(StepperAction::Remove("Whep".into()));
+                }
+                whep_active = new_value;
+            }
+            Ui::same_line();
+            if let Some(new_value) = Ui::toggle("Camera", device_active, None) {
+                if new_value {
+                    devices = list_devices().unwrap_or_else(|err| {
+                        Log::warn(format!("Unable to list the capture devices : {:?}", err));
+                        vec![]
+                    });
+                    for device in &devices {
+                        Log::diag(format!("{} : {} ({} modes)", device.class, device.name, device.modes.len()));
+                    }
+                    // open the first camera with its own mode, the microphones may feed back
+                    camera = devices.iter().position(CaptureDevice::is_video);
+                    camera_mode = None;
+                    microphone = None;
+                    let video_device = device_video(&devices, camera, camera_mode, microphone);
+                    statuses.insert("Device", video_device.status_handle());
+                    audios.insert("Device", video_device.playback.audio_handle());
+                    sk.push_action(StepperAction::add("Device", video_device));
+                } else {
+                    sk.push_action(StepperAction::Remove("Device".into()));
+                }
+                device_active = new_value;
+            }
+            Ui::same_line();
+            if let Some(new_value) = Ui::toggle("Desktop", desktop_active, None) {
+                if new_value {
+                    // capture the X display of $DISPLAY with its cursor, videos/desktop.txt may name another display
+                    let target = CaptureTarget::Display(external_text(sk, "videos/desktop.txt"));
+                    let mut video_desktop = Video1::new(VideoType::ScreenCapture { target, cursor: true, max_fps: 30 });
+                    video_desktop.auto_size = true;
+                    video_desktop.transform_screen =
+                        Matrix::tr(&(Vec3::new(13.5, 2.0, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    statuses.insert("Desktop", video_desktop.status_handle());
+                    audios.insert("Desktop", video_desktop.playback.audio_handle());
+                    sk.push_action(StepperAction::add("Desktop", video_desktop));
+                } else {
+                    sk.push_action(StepperAction::Remove("Desktop".into()));
+                }
+                desktop_active = new_value;
+            }
+            Ui::same_line();
+            if let Some(new_value) = Ui::toggle("Test pattern", test_pattern_active, None) {
+                if new_value {
+                    let video_test_pattern = test_pattern_video();
+                    statuses.insert("TestPattern", video_test_pattern.status_handle());
+                    audios.insert("TestPattern", video_test_pattern.playback.audio_handle());
+                    sk.push_action(StepperAction::add("TestPattern", video_test_pattern));
+                } else {
+                    sk.push_action(StepperAction::Remove("TestPattern".into()));
+                }
+                test_pattern_active = new_value;
+            }
 
             Ui::next_line();
             if let Some(new_value) = Ui::toggle("Playbin MP4", playbin_h264_active, None) {
@@ -245,12 +539,44 @@ pub fn launch(mut sk: Sk, event_loop: EventLoop<StepperAction>, _is_testing: boo
                     let mut video_h264 = Video2::new(uri_fmt, v3_enabled);
                     video_h264.transform_screen =
                         Matrix::tr(&(Vec3::new(-0.5, 0.8, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    statuses.insert("PlaybinH264", video_h264.status_handle());
+                    audios.insert("PlaybinH264", video_h264.playback.audio_handle());
                     sk.push_action(StepperAction::add("PlaybinH264", video_h264));
                 } else {
                     sk.push_action(StepperAction::Remove("PlaybinH264".into()));
                 }
                 playbin_h264_active = new_value;
             }
+            Ui::same_line();
+            if let Some(new_value) = Ui::toggle("Playlist M3U", playlist_active, None) {
+                if new_value {
+                    let playlist = if let Some(dir_path) = get_external_path(sk.get_sk_info_clone()) {
+                        let file_path = dir_path.join("videos/playlist.m3u8");
+                        match parse_m3u_file(&file_path) {
+                            Ok(entries) => entries.into_iter().map(|entry| entry.location).collect(),
+                            Err(err) => {
+                                Log::warn(format!("No playlist {:?} : {:?}", file_path, err));
+                                vec![]
+                            }
+                        }
+                    } else {
+                        Log::warn(format!("No external path{}", "!"));
+                        vec![]
+                    };
+                    // launch the playlist in loop
+                    let mut video_playlist = Video2::new("!!!!!!!Empty playlist".into(), v3_enabled);
+                    video_playlist.playback.playlist = playlist;
+                    video_playlist.playback.looping = true;
+                    video_playlist.transform_screen =
+                        Matrix::tr(&(Vec3::new(-2.5, 0.8, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    statuses.insert("PlaybinPlaylist", video_playlist.status_handle());
+                    audios.insert("PlaybinPlaylist", video_playlist.playback.audio_handle());
+                    sk.push_action(StepperAction::add("PlaybinPlaylist", video_playlist));
+                } else {
+                    sk.push_action(StepperAction::Remove("PlaybinPlaylist".into()));
+                }
+                playlist_active = new_value;
+            }
 
             Ui::next_line();
             if let Some(new_value) = Ui::toggle("Video MP4", video_h264_active, None) {
@@ -272,6 +598,8 @@ pub fn launch(mut sk: Sk, event_loop: EventLoop<StepperAction>, _is_testing: boo
                     let mut video_h264 = Video1::new(VideoType::H264File { uri: uri_fmt });
                     video_h264.transform_screen =
                         Matrix::tr(&(Vec3::new(-0.5, 0.8, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    statuses.insert("VideoH264", video_h264.status_handle());
+                    audios.insert("VideoH264", video_h264.playback.audio_handle());
                     sk.push_action(StepperAction::add("VideoH264", video_h264));
                 } else {
                     sk.push_action(StepperAction::Remove("VideoH264".into()));
@@ -298,14 +626,44 @@ pub fn launch(mut sk: Sk, event_loop: EventLoop<StepperAction>, _is_testing: boo
                     let mut video_mkv_vp8 = Video1::new(VideoType::VP8File { uri: uri_fmt });
                     video_mkv_vp8.transform_screen =
                         Matrix::tr(&(Vec3::new(1.5, 0.8, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
-                    video_mkv_vp8.width = 854;
-                    video_mkv_vp8.height = 480;
+                    video_mkv_vp8.auto_size = true;
+                    statuses.insert("Videomkv_vp8", video_mkv_vp8.status_handle());
+                    audios.insert("Videomkv_vp8", video_mkv_vp8.playback.audio_handle());
                     sk.push_action(StepperAction::add("Videomkv_vp8", video_mkv_vp8));
                 } else {
                     sk.push_action(StepperAction::Remove("Videomkv_vp8".into()));
                 }
                 video_mkv_vp8_active = new_value;
             }
+            Ui::same_line();
+            if let Some(new_value) = Ui::toggle("Video VP9", video_mkv_vp9_active, None) {
+                if new_value {
+                    let uri_fmt = if let Some(dir_path) = get_external_path(sk.get_sk_info_clone()) {
+                        let file_path = dir_path.join("videos/sintel_trailer-480p-vp9.webm");
+                        if file_path.is_file() {
+                            Log::diag(format!("File VP9 : {:?}", file_path));
+                            file_path.to_str().unwrap().into()
+                        } else {
+                            Log::warn(format!("No file VP9 : {:?}", file_path));
+                            "!!!!!!No File".into()
+                        }
+                    } else {
+                        Log::warn(format!("No external path{}", "!"));
+                        "!!!!!!No external path".into()
+                    };
+                    // launch video_mkv_vp9
+                    let mut video_mkv_vp9 = Video1::new(VideoType::VP9File { uri: uri_fmt });
+                    video_mkv_vp9.transform_screen =
+                        Matrix::tr(&(Vec3::new(3.5, 0.8, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    video_mkv_vp9.auto_size = true;
+                    statuses.insert("Videomkv_vp9", video_mkv_vp9.status_handle());
+                    audios.insert("Videomkv_vp9", video_mkv_vp9.playback.audio_handle());
+                    sk.push_action(StepperAction::add("Videomkv_vp9", video_mkv_vp9));
+                } else {
+                    sk.push_action(StepperAction::Remove("Videomkv_vp9".into()));
+                }
+                video_mkv_vp9_active = new_value;
+            }
             Ui::next_line();
             if let Some(new_value) = Ui::toggle("Video MP4(dec)", video_h264_dec_active, None) {
                 if new_value {
@@ -326,6 +684,8 @@ pub fn launch(mut sk: Sk, event_loop: EventLoop<StepperAction>, _is_testing: boo
                     let mut video_h264 = Video1::new(VideoType::Decodebin { uri: uri_fmt, v3_enabled });
                     video_h264.transform_screen =
                         Matrix::tr(&(Vec3::new(-0.5, -0.4, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    statuses.insert("VideoH264_dec", video_h264.status_handle());
+                    audios.insert("VideoH264_dec", video_h264.playback.audio_handle());
                     sk.push_action(StepperAction::add("VideoH264_dec", video_h264));
                 } else {
                     sk.push_action(StepperAction::Remove("VideoH264_dec".into()));
@@ -352,6 +712,8 @@ pub fn launch(mut sk: Sk, event_loop: EventLoop<StepperAction>, _is_testing: boo
                     let mut video_vp8 = Video1::new(VideoType::Decodebin { uri: uri_fmt, v3_enabled });
                     video_vp8.transform_screen =
                         Matrix::tr(&(Vec3::new(1.5, -0.4, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    statuses.insert("Videovp8_dec", video_vp8.status_handle());
+                    audios.insert("Videovp8_dec", video_vp8.playback.audio_handle());
                     sk.push_action(StepperAction::add("Videovp8_dec", video_vp8));
                 } else {
                     sk.push_action(StepperAction::Remove("Videovp8_dec".into()));
@@ -368,13 +730,223 @@ pub fn launch(mut sk: Sk, event_loop: EventLoop<StepperAction>, _is_testing: boo
                     });
                     video_vp8.transform_screen =
                         Matrix::tr(&(Vec3::new(3.5, -0.4, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    video_vp8.auto_size = true;
+                    statuses.insert("video_VP8_dec", video_vp8.status_handle());
+                    audios.insert("video_VP8_dec", video_vp8.playback.audio_handle());
                     sk.push_action(StepperAction::add("video_VP8_dec", video_vp8));
                 } else {
                     sk.push_action(StepperAction::Remove("video_VP8_dec".into()));
                 }
                 video_vp8_https_dec_active = new_value;
             }
+            Ui::same_line();
+            if let Some(new_value) = Ui::toggle("HLS/DASH", adaptive_active, None) {
+                if new_value {
+                    let uri = external_text(sk, "videos/adaptive.txt").unwrap_or_else(|| DEFAULT_ADAPTIVE_URI.into());
+                    // playbin3 is needed by the adaptivedemux2 elements
+                    let mut video_adaptive = Video2::new(uri, true);
+                    video_adaptive.transform_screen =
+                        Matrix::tr(&(Vec3::new(5.5, -0.4, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0));
+                    video_adaptive.auto_size = true;
+                    statuses.insert("Adaptive", video_adaptive.status_handle());
+                    audios.insert("Adaptive", video_adaptive.playback.audio_handle());
+                    sk.push_action(StepperAction::add("Adaptive", video_adaptive));
+                } else {
+                    sk.push_action(StepperAction::Remove("Adaptive".into()));
+                }
+                adaptive_active = new_value;
+            }
             Ui::next_line();
+            if device_active {
+                // pick the camera, its mode and the microphone of the "Camera" screen
+                let mut changed = false;
+                let (cameras, mics): (Vec<_>, Vec<_>) =
+                    devices.iter().enumerate().partition(|(_, device)| device.is_video());
+                for (index, device) in cameras {
+                    let selected = camera == Some(index);
+                    if Ui::radio_img(&device.name, selected, &radio_off, &radio_on, UiBtnLayout::Left, None)
+                        && !selected
+                    {
+                        (camera, camera_mode) = (Some(index), None);
+                        changed = true;
+                    }
+                    Ui::same_line();
+                }
+                Ui::next_line();
+                if let Some(modes) = camera.and_then(|index| devices.get(index)).map(|device| &device.modes) {
+                    let auto = camera_mode.is_none();
+                    if Ui::radio_img("Auto", auto, &radio_off, &radio_on, UiBtnLayout::Left, None) && !auto {
+                        camera_mode = None;
+                        changed = true;
+                    }
+                    for (index, mode) in modes.iter().enumerate().take(8) {
+                        Ui::same_line();
+                        let selected = camera_mode == Some(index);
+                        if Ui::radio_img(mode.label(), selected, &radio_off, &radio_on, UiBtnLayout::Left, None)
+                            && !selected
+                        {
+                            camera_mode = Some(index);
+                            changed = true;
+                        }
+                    }
+                    Ui::next_line();
+                }
+                let no_mic = microphone.is_none();
+                if Ui::radio_img("No mic", no_mic, &radio_off, &radio_on, UiBtnLayout::Left, None) && !no_mic {
+                    microphone = None;
+                    changed = true;
+                }
+                for (index, device) in mics {
+                    Ui::same_line();
+                    let selected = microphone == Some(index);
+                    if Ui::radio_img(&device.name, selected, &radio_off, &radio_on, UiBtnLayout::Left, None)
+                        && !selected
+                    {
+                        microphone = Some(index);
+                        changed = true;
+                    }
+                }
+                Ui::next_line();
+                if changed {
+                    sk.push_action(StepperAction::Remove("Device".into()));
+                    let video_device = device_video(&devices, camera, camera_mode, microphone);
+                    statuses.insert("Device", video_device.status_handle());
+                    audios.insert("Device", video_device.playback.audio_handle());
+                    sk.push_action(StepperAction::add("Device", video_device));
+                }
+            }
+            Ui::hseparator();
+            if Ui::button(format!("Screen: {}", screens[controlled_screen]), None) {
+                controlled_screen = (controlled_screen + 1) % screens.len();
+            }
+            Ui::same_line();
+            let screen_id = screens[controlled_screen];
+            for (label, command) in [
+                ("Play", PlayerCommand::Play),
+                ("Pause", PlayerCommand::Pause),
+                ("-10s", PlayerCommand::SeekBy(-10.0)),
+                ("+10s", PlayerCommand::SeekBy(10.0)),
+                ("Step", PlayerCommand::FrameStep),
+                ("x0.5", PlayerCommand::Rate(0.5)),
+                ("x1", PlayerCommand::Rate(1.0)),
+                ("x2", PlayerCommand::Rate(2.0)),
+                ("Reverse", PlayerCommand::Rate(-1.0)),
+            ] {
+                if Ui::button(label, None) {
+                    sk.push_action(command.to_event(screen_id));
+                }
+                Ui::same_line();
+            }
+            Ui::next_line();
+            // the treble is the last band of the equalizer of the screen (3 or 10 bands)
+            let treble =
+                audios.get(screen_id).map_or(2, |audio| audio.lock().unwrap().equalizer.len().saturating_sub(1));
+            for (label, command) in [
+                ("Vol 50%", PlayerCommand::Volume(0.5)),
+                ("Vol 100%", PlayerCommand::Volume(1.0)),
+                ("Mute", PlayerCommand::Mute(true)),
+                ("Unmute", PlayerCommand::Mute(false)),
+                ("Bass +6dB", PlayerCommand::Equalizer(0, 6.0)),
+                ("Bass 0dB", PlayerCommand::Equalizer(0, 0.0)),
+                ("Treble -12dB", PlayerCommand::Equalizer(treble, -12.0)),
+                ("Treble 0dB", PlayerCommand::Equalizer(treble, 0.0)),
+                ("Subs on", PlayerCommand::Subtitles(true)),
+                ("Subs off", PlayerCommand::Subtitles(false)),
+            ] {
+                if Ui::button(label, None) {
+                    sk.push_action(command.to_event(screen_id));
+                }
+                Ui::same_line();
+            }
+            Ui::next_line();
+            if let Some(status) = statuses.get(screen_id) {
+                let status = status.lock().unwrap().clone();
+                Ui::progress_bar(status.progress().unwrap_or(0.0), None);
+                Ui::label(
+                    format!("{} {:?} x{} ({}%)", status.time_text(), status.state, status.rate, status.buffering),
+                    None,
+                    true,
+                );
+                if !status.decoders.is_empty() {
+                    Ui::label(format!("decoders: {}", status.decoders.join(", ")), None, true);
+                }
+                if let Some(stats) = status.rtp_stats {
+                    Ui::label(
+                        format!(
+                            "rtp: {} packets, {} lost ({:.1}%), {} late, jitter {:.1}ms",
+                            stats.pushed,
+                            stats.lost,
+                            stats.loss_percent(),
+                            stats.late,
+                            stats.avg_jitter as f64 / 1_000_000.0
+                        ),
+                        None,
+                        true,
+                    );
+                }
+                if let Some(stats) = status.srt_stats {
+                    Ui::label(
+                        format!(
+                            "srt: rtt {:.1}ms, {} packets, {} lost, {} dropped, {} retransmitted, {:.1}Mb/s",
+                            stats.rtt,
+                            stats.packets_received,
+                            stats.packets_lost,
+                            stats.packets_dropped,
+                            stats.packets_retransmitted,
+                            stats.receive_rate
+                        ),
+                        None,
+                        true,
+                    );
+                }
+                if let Some(adaptive) = &status.adaptive {
+                    let current = adaptive.current.and_then(|index| adaptive.variants.get(index));
+                    Ui::label(
+                        format!(
+                            "variant: {} (bandwidth {:.1}Mb/s)",
+                            current.map(|variant| variant.label()).unwrap_or_else(|| "?".into()),
+                            adaptive.bandwidth.unwrap_or(0) as f64 / 1_000_000.0
+                        ),
+                        None,
+                        true,
+                    );
+                    let auto = adaptive.selected.is_none();
+                    if Ui::radio_img("Auto", auto, &radio_off, &radio_on, UiBtnLayout::Left, None) {
+                        sk.push_action(PlayerCommand::Variant(None).to_event(screen_id));
+                    }
+                    for (index, variant) in adaptive.variants.iter().enumerate() {
+                        Ui::same_line();
+                        let selected = adaptive.selected == Some(index);
+                        if Ui::radio_img(variant.label(), selected, &radio_off, &radio_on, UiBtnLayout::Left, None) {
+                            sk.push_action(PlayerCommand::Variant(Some(index)).to_event(screen_id));
+                        }
+                    }
+                }
+                // cycle through the tracks of the media (needs Decodebin3)
+                for kind in [TrackKind::Audio, TrackKind::Video, TrackKind::Text] {
+                    let count = status.tracks.iter().filter(|track| track.kind == kind).count();
+                    if count < 2 {
+                        continue;
+                    }
+                    let current = status.selected_track(kind);
+                    let label = match current {
+                        Some(track) => format!(
+                            "{} {}/{} {}",
+                            kind.name(),
+                            track.index + 1,
+                            count,
+                            track.language.as_deref().unwrap_or("")
+                        ),
+                        None => format!("{} -/{}", kind.name(), count),
+                    };
+                    if Ui::button(label, None) {
+                        let next = current.map(|track| (track.index + 1) % count).unwrap_or(0);
+                        sk.push_action(PlayerCommand::SelectTrack(kind, next).to_event(screen_id));
+                    }
+                    Ui::same_line();
+                }
+                Ui::next_line();
+            }
             Ui::hseparator();
             if Ui::button("Exit", Some(Vec2::new(0.10, 0.10))) {
                 sk.quit(None);
@@ -0,0 +1,44 @@
+use anyhow::{anyhow, Ok};
+use gstreamer::{
+    glib::EnumClass,
+    prelude::{ElementExt, GstBinExtManual, ObjectExt},
+    Bin, Caps, Element, ElementFactory, Fraction, GhostPad,
+};
+
+/// Set an enum property by the nick of its value. An unknown nick is an error listing the valid ones.
+fn set_enum_property(element: &Element, property: &str, nick: &str) -> Result<(), anyhow::Error> {
+    let pspec = element.find_property(property).ok_or_else(|| anyhow!("No property {}", property))?;
+    let class = EnumClass::with_type(pspec.value_type()).ok_or_else(|| anyhow!("{} is not an enum", property))?;
+    let Some(value) = class.to_value_by_nick(nick) else {
+        let nicks: Vec<&str> = class.values().iter().map(|value| value.nick()).collect();
+        return Err(anyhow!("Unknown {} {} (one of {})", property, nick, nicks.join(", ")));
+    };
+    element.set_property_from_value(property, &value);
+    Ok(())
+}
+
+/// A bin `videotestsrc is-live=true pattern=<pattern> ! capsfilter` with a src pad. `pattern` is a nick of
+/// videotestsrc (smpte, ball, snow, checkers-8, ...).
+pub fn test_pattern_bin(pattern: &str, width: i32, height: i32, framerate: i32) -> Result<Bin, anyhow::Error> {
+    let bin = Bin::new();
+    let videotestsrc = ElementFactory::make("videotestsrc").property("is-live", true).build()?;
+    set_enum_property(&videotestsrc, "pattern", pattern)?;
+    let caps = Caps::builder("video/x-raw")
+        .field("width", width)
+        .field("height", height)
+        .field("framerate", Fraction::new(framerate.max(1), 1))
+        .build();
+    let capsfilter = ElementFactory::make("capsfilter").property("caps", caps).build()?;
+    bin.add_many([&videotestsrc, &capsfilter])?;
+    videotestsrc.link(&capsfilter)?;
+    let src_pad = capsfilter.static_pad("src").expect("capsfilter has no src pad");
+    bin.add_pad(&GhostPad::with_target(&src_pad)?)?;
+    Ok(bin)
+}
+
+/// `audiotestsrc is-live=true wave=<tone>`, `tone` is a nick of audiotestsrc (sine, ticks, pink-noise, ...)
+pub fn test_tone_source(tone: &str) -> Result<Element, anyhow::Error> {
+    let audiotestsrc = ElementFactory::make("audiotestsrc").property("is-live", true).build()?;
+    set_enum_property(&audiotestsrc, "wave", tone)?;
+    Ok(audiotestsrc)
+}
@@ -0,0 +1,634 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Ok};
+use gstreamer::{
+    prelude::{Cast, ElementExt, GstObjectExt},
+    Bus, ClockTime, Element, MessageType,
+};
+
+use crate::{
+    adaptive::{
+        adaptive_demuxers, connect_adaptive_demuxers, current_bandwidth, is_manifest, load_variants, set_bitrate_range,
+        AdaptiveStatus, ADAPTIVE_STATISTICS,
+    },
+    audio::{apply_audio_settings, AudioSettings, AudioSettingsHandle},
+    player::{error_origin, SinkContext, SourceStrategy},
+    playlist::{to_path, to_uri},
+    status::{PlaybackStatus, PlaybackStatusHandle, StreamState},
+    subtitles::{find_sidecar, parse_subtitle_file, Subtitles, SubtitlesHandle},
+    tracks::{self, TrackInfo, TrackKind},
+    transport::{self, PlayerCommand},
+};
+use stereokit_rust::system::Log;
+
+/// The pipeline of a SourceStrategy and everything its bus drives, without StereoKit: the states of the status, the
+/// end of the stream (playlist, loop), the errors (decoder fallback, reconnection), the tracks, the variants and the
+/// transport commands. MediaPlayer shows it on a screen, the tests step it alone with offscreen sinks.
+pub struct Playback<S: SourceStrategy> {
+    /// Name in the logs
+    pub id: String,
+    /// Where the media comes from
+    pub source: S,
+    /// A SRT or WebVTT file to show instead of the embedded subtitles. If None, a .srt or .vtt file with the name of
+    /// the media is used if it exists.
+    pub subtitle_file: Option<String>,
+    /// Loop the media (seamless segment seek) or the playlist
+    pub looping: bool,
+    /// Locations (file paths or uris) played one after the other on this screen. The first one replaces the location
+    /// of the source. See playlist::parse_m3u_file()
+    pub playlist: Vec<String>,
+    playlist_index: usize,
+    segment_armed: bool,
+    /// The sinks given to start(), every new pipeline is built with them
+    sinks: Option<SinkContext>,
+    pipeline: Option<Element>,
+    bus: Option<Bus>,
+    status: PlaybackStatusHandle,
+    rate: f64,
+    audio: AudioSettingsHandle,
+    track_selector: Option<Element>,
+    subtitles: SubtitlesHandle,
+    /// When to launch a new pipeline for a lost source
+    reconnect_at: Option<Instant>,
+}
+
+impl<S: SourceStrategy> Playback<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            id: S::NAME.to_string(),
+            source,
+            subtitle_file: None,
+            looping: false,
+            playlist: vec![],
+            playlist_index: 0,
+            segment_armed: false,
+            sinks: None,
+            pipeline: None,
+            bus: None,
+            status: Arc::new(Mutex::new(PlaybackStatus::default())),
+            rate: 1.0,
+            audio: Arc::new(Mutex::new(AudioSettings::default())),
+            track_selector: None,
+            subtitles: Arc::new(Mutex::new(Subtitles::default())),
+            reconnect_at: None,
+        }
+    }
+
+    /// Build the first pipeline (on the first entry of the playlist if any) with these sinks. Their status, audio
+    /// settings and subtitles are replaced by the ones of the playback.
+    pub fn start(&mut self, id: &str, mut sinks: SinkContext) -> Result<(), anyhow::Error> {
+        self.id = id.to_string();
+        sinks.status = self.status.clone();
+        sinks.audio = self.audio.clone();
+        sinks.subtitles = self.subtitles.clone();
+        self.sinks = Some(sinks);
+
+        if let Some(first) = self.playlist.first().cloned() {
+            if let Err(err) = self.source.set_location(&first, None) {
+                Log::warn(format!("Playlist ignored by {} : {:?}", self.id, err));
+            }
+        }
+        if let Some(location) = self.source.location() {
+            self.load_sidecar(&to_path(&location));
+        }
+        self.launch()
+    }
+
+    /// Launch the new pipeline of a lost source when it's time, query the position, let the source update the
+    /// status and handle one message of the bus
+    pub fn step(&mut self) {
+        if self.reconnect_at.is_some_and(|reconnect_at| Instant::now() >= reconnect_at) {
+            self.reconnect();
+        }
+        if let Some(pipeline) = &self.pipeline {
+            let mut status = self.status.lock().unwrap();
+            status.query(pipeline);
+            self.source.update_status(pipeline, &mut status);
+        }
+        self.check_bus();
+    }
+
+    /// Close the pipeline, the status gets back to its default
+    pub fn stop(&mut self) {
+        if let Some(pipeline) = &self.pipeline {
+            match pipeline.set_state(gstreamer::State::Paused) {
+                Err(err) => Log::err(format!("Error when pausing pipeline : {:?}", err)),
+                _ => {
+                    if let Err(err) = pipeline.set_state(gstreamer::State::Null) {
+                        Log::err(format!("Error when closing pipeline : {:?}", err));
+                    }
+                }
+            }
+        }
+        self.bus = None;
+        self.pipeline = None;
+        self.track_selector = None;
+        self.reconnect_at = None;
+        self.source.shutdown();
+        *self.status.lock().unwrap() = PlaybackStatus::default();
+    }
+
+    /// The running pipeline if any
+    pub fn pipeline(&self) -> Option<&Element> {
+        self.pipeline.as_ref()
+    }
+
+    /// Start or resume the playback
+    pub fn play(&mut self) -> Result<(), anyhow::Error> {
+        self.control(PlayerCommand::Play)
+    }
+
+    /// Pause the playback
+    pub fn pause(&mut self) -> Result<(), anyhow::Error> {
+        self.control(PlayerCommand::Pause)
+    }
+
+    /// Seek to `position` seconds
+    pub fn seek(&mut self, position: f64) -> Result<(), anyhow::Error> {
+        self.control(PlayerCommand::Seek(position))
+    }
+
+    /// Pause and show the next frame (the previous one if the rate is negative)
+    pub fn frame_step(&mut self) -> Result<(), anyhow::Error> {
+        self.control(PlayerCommand::FrameStep)
+    }
+
+    /// Set the playback rate (1.0 is normal speed, negative values play backward)
+    pub fn set_rate(&mut self, rate: f64) -> Result<(), anyhow::Error> {
+        self.control(PlayerCommand::Rate(rate))
+    }
+
+    /// The current playback rate
+    pub fn get_rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Set the volume (0.0 to 10.0, 1.0 is the original level)
+    pub fn set_volume(&mut self, volume: f64) -> Result<(), anyhow::Error> {
+        self.audio.lock().unwrap().volume = volume.clamp(0.0, 10.0);
+        self.apply_audio_settings();
+        Ok(())
+    }
+
+    /// Mute or unmute the screen
+    pub fn set_mute(&mut self, mute: bool) -> Result<(), anyhow::Error> {
+        self.audio.lock().unwrap().mute = mute;
+        self.apply_audio_settings();
+        Ok(())
+    }
+
+    /// Set the gain in dB (-24.0 to 12.0) of a band of the equalizer
+    pub fn set_equalizer_band(&mut self, band: usize, gain: f64) -> Result<(), anyhow::Error> {
+        self.audio.lock().unwrap().set_band(band, gain)?;
+        self.apply_audio_settings();
+        Ok(())
+    }
+
+    /// Replace all the audio settings. The number of equalizer bands (3 or 10) is only used when the pipeline is
+    /// created.
+    pub fn set_audio_settings(&mut self, settings: AudioSettings) {
+        *self.audio.lock().unwrap() = settings;
+        self.apply_audio_settings();
+    }
+
+    /// A copy of the current audio settings
+    pub fn get_audio_settings(&self) -> AudioSettings {
+        self.audio.lock().unwrap().clone()
+    }
+
+    /// The audio settings shared with the sinks
+    pub fn audio_handle(&self) -> AudioSettingsHandle {
+        self.audio.clone()
+    }
+
+    fn apply_audio_settings(&self) {
+        if let Some(pipeline) = &self.pipeline {
+            apply_audio_settings(pipeline, &self.audio.lock().unwrap());
+        }
+    }
+
+    /// The tracks of the media, empty until decodebin3/playbin3 posted its stream collection
+    pub fn get_tracks(&self) -> Vec<TrackInfo> {
+        self.status.lock().unwrap().tracks.clone()
+    }
+
+    /// Select the track `index` (see TrackInfo::index) of this kind, the selected tracks of the other kinds are kept.
+    /// Selecting an embedded text track replaces the subtitle file if any.
+    pub fn select_track(&mut self, kind: TrackKind, index: usize) -> Result<(), anyhow::Error> {
+        let stream_ids = tracks::selection_with(&self.status.lock().unwrap().tracks, kind, index)?;
+        self.select_tracks(&stream_ids)?;
+        if kind == TrackKind::Text {
+            let mut subtitles = self.subtitles.lock().unwrap();
+            subtitles.clear_embedded();
+            subtitles.set_sidecar(None);
+        }
+        Ok(())
+    }
+
+    /// Select exactly these tracks (stream ids of TrackInfo)
+    pub fn select_tracks(&mut self, stream_ids: &[String]) -> Result<(), anyhow::Error> {
+        let Some(selector) = &self.track_selector else {
+            return Err(anyhow!("{} has no stream collection, track selection needs decodebin3 or playbin3", self.id));
+        };
+        Log::diag(format!("{} selecting tracks {:?}", self.id, stream_ids));
+        tracks::select_streams(selector, stream_ids)
+    }
+
+    /// The subtitles shared with the text sink
+    pub fn subtitles_handle(&self) -> SubtitlesHandle {
+        self.subtitles.clone()
+    }
+
+    /// Show or hide the subtitles
+    pub fn set_subtitles_visible(&mut self, visible: bool) {
+        self.subtitles.lock().unwrap().visible = visible;
+    }
+
+    /// Show the cues of this SRT or WebVTT file instead of the embedded subtitles
+    pub fn load_subtitle_file(&mut self, path: impl AsRef<Path>) -> Result<(), anyhow::Error> {
+        let cues = parse_subtitle_file(path.as_ref())?;
+        Log::diag(format!("{} : {} subtitles in {:?}", self.id, cues.len(), path.as_ref()));
+        self.subtitles.lock().unwrap().set_sidecar(Some(cues));
+        Ok(())
+    }
+
+    /// Get back to the embedded subtitles
+    pub fn unload_subtitle_file(&mut self) {
+        self.subtitles.lock().unwrap().set_sidecar(None);
+    }
+
+    /// Load `subtitle_file` or the sidecar file of the media if any
+    fn load_sidecar(&mut self, media_path: &str) {
+        self.unload_subtitle_file();
+        let Some(path) = self.subtitle_file.as_ref().map(PathBuf::from).or_else(|| find_sidecar(media_path)) else {
+            return;
+        };
+        if let Err(err) = self.load_subtitle_file(&path) {
+            Log::warn(format!("{} unable to load subtitles {:?} : {:?}", self.id, path, err));
+        }
+    }
+
+    /// The subtitles to show at the current position. The embedded cues already shown are forgotten while playing
+    /// forward.
+    pub fn subtitle_text(&self) -> Option<String> {
+        let position = self.status.lock().unwrap().position?;
+        let mut subtitles = self.subtitles.lock().unwrap();
+        if self.rate > 0.0 {
+            subtitles.prune_embedded(position);
+        }
+        subtitles.text_at(position)
+    }
+
+    /// Apply a command, this is also what the PlayerCommand events are doing
+    pub fn control(&mut self, command: PlayerCommand) -> Result<(), anyhow::Error> {
+        match command {
+            PlayerCommand::Volume(volume) => return self.set_volume(volume),
+            PlayerCommand::Mute(mute) => return self.set_mute(mute),
+            PlayerCommand::Equalizer(band, gain) => return self.set_equalizer_band(band, gain),
+            PlayerCommand::SelectTrack(kind, index) => return self.select_track(kind, index),
+            PlayerCommand::Variant(index) => return self.select_variant(index),
+            PlayerCommand::Subtitles(visible) => {
+                self.set_subtitles_visible(visible);
+                return Ok(());
+            }
+            _ => {}
+        }
+        let Some(pipeline) = &self.pipeline else {
+            return Err(anyhow!("{} has no running pipeline", self.id));
+        };
+        transport::apply(pipeline, &mut self.rate, command)?;
+        self.status.lock().unwrap().rate = self.rate;
+        Ok(())
+    }
+
+    /// Play this variant of a HLS or DASH stream (see PlaybackStatus::adaptive), None lets the demuxer follow the
+    /// bandwidth
+    pub fn select_variant(&mut self, index: Option<usize>) -> Result<(), anyhow::Error> {
+        let range = {
+            let mut status = self.status.lock().unwrap();
+            let Some(adaptive) = status.adaptive.as_mut() else {
+                return Err(anyhow!("{} is not playing a HLS or DASH stream", self.id));
+            };
+            if let Some(index) = index {
+                let variant = adaptive.variants.get(index).ok_or(anyhow!("No variant {}", index))?;
+                Log::diag(format!("{} selecting variant {}", self.id, variant.label()));
+            }
+            adaptive.selected = index;
+            adaptive.bitrate_range()
+        };
+        if let Some(pipeline) = &self.pipeline {
+            for demuxer in adaptive_demuxers(pipeline) {
+                set_bitrate_range(&demuxer, range);
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the variants of a HLS or DASH location in a thread, a new media gets back to the automatic selection
+    fn read_variants(&mut self) {
+        let manifest =
+            self.source.location().filter(|location| is_manifest(location)).map(|location| to_uri(&location));
+        self.status.lock().unwrap().adaptive = manifest.as_ref().map(|_| AdaptiveStatus::default());
+        if let Some(manifest) = manifest {
+            load_variants(manifest, self.status.clone());
+        }
+    }
+
+    /// A download of the adaptive demuxer: the variant being played and the measured bandwidth
+    fn on_adaptive_download(&self, uri: &str, demuxer: Option<Element>) {
+        let mut status = self.status.lock().unwrap();
+        let video_size = status.video_size;
+        let Some(adaptive) = status.adaptive.as_mut() else {
+            return;
+        };
+        if let Some(index) = adaptive.variant_of(uri, video_size) {
+            if adaptive.current != Some(index) {
+                Log::info(format!("{} plays variant {}", self.id, adaptive.variants[index].label()));
+                adaptive.current = Some(index);
+            }
+        }
+        if let Some(bandwidth) = demuxer.as_ref().and_then(current_bandwidth) {
+            adaptive.bandwidth = Some(bandwidth);
+        }
+    }
+
+    /// A copy of the current playback status
+    pub fn get_status(&self) -> PlaybackStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// The status shared with the sinks and the application
+    pub fn status_handle(&self) -> PlaybackStatusHandle {
+        self.status.clone()
+    }
+
+    /// Build the pipeline of the source
+    fn launch(&mut self) -> Result<(), anyhow::Error> {
+        let sinks = self.sinks.as_ref().ok_or(anyhow!("{} is not started", self.id))?;
+        let pipeline = self.source.build(sinks)?;
+        connect_adaptive_demuxers(&pipeline, self.status.clone());
+        self.bus = Some(pipeline.bus().expect("Pipeline without bus. Shouldn't happen!"));
+        self.pipeline = Some(pipeline);
+        self.read_variants();
+        Ok(())
+    }
+
+    /// At the end of the stream: next entry of the playlist, seamless loop or close the pipeline
+    fn on_eos(&mut self) -> Result<(), anyhow::Error> {
+        if self.playlist.len() > 1 && (self.looping || self.playlist_index + 1 < self.playlist.len()) {
+            self.playlist_index = (self.playlist_index + 1) % self.playlist.len();
+            return self.open_playlist_entry(self.playlist_index);
+        }
+        if let Some(delay) = self.source.reconnect_delay() {
+            // the server ended the live stream
+            self.schedule_reconnect(delay);
+            return Ok(());
+        }
+        if let Some(pipeline) = &self.pipeline {
+            if self.looping {
+                // the segment seek has been lost (a seek from the user), we restart with a flush
+                return transport::segment_seek(pipeline, self.rate, true);
+            }
+            pipeline.set_state(gstreamer::State::Null)?;
+        }
+        self.status.lock().unwrap().state = StreamState::Eos;
+        self.bus = None;
+        self.pipeline = None;
+        self.track_selector = None;
+        Ok(())
+    }
+
+    /// Close the pipeline of a lost source and launch a new one in `delay` seconds
+    fn schedule_reconnect(&mut self, delay: f64) {
+        if let Some(pipeline) = self.pipeline.take() {
+            if let Err(err) = pipeline.set_state(gstreamer::State::Null) {
+                Log::err(format!("Error when closing pipeline : {:?}", err));
+            }
+        }
+        self.bus = None;
+        self.track_selector = None;
+        self.source.shutdown();
+        self.reconnect_at = Some(Instant::now() + Duration::from_secs_f64(delay.max(0.0)));
+        self.status.lock().unwrap().state = StreamState::Reconnecting;
+        Log::warn(format!("{} lost its source, reconnection in {:.1}s", self.id, delay));
+    }
+
+    /// Launch a new pipeline for the lost source. The sinks are kept.
+    fn reconnect(&mut self) {
+        self.reconnect_at = None;
+        self.segment_armed = false;
+        {
+            let mut status = self.status.lock().unwrap();
+            status.position = None;
+            status.duration = None;
+            status.tracks.clear();
+            status.decoders.clear();
+        }
+        self.subtitles.lock().unwrap().clear_embedded();
+        Log::info(format!("{} reconnecting", self.id));
+        if let Err(err) = self.launch().and_then(|_| self.play()) {
+            Log::warn(format!("{} unable to reconnect : {:?}", self.id, err));
+            if let Some(delay) = self.source.reconnect_delay() {
+                self.schedule_reconnect(delay);
+            }
+        }
+    }
+
+    /// Play this entry of the playlist. The source switches the running pipeline or a new pipeline is built with
+    /// the same sinks.
+    fn open_playlist_entry(&mut self, index: usize) -> Result<(), anyhow::Error> {
+        let location = self.playlist.get(index).ok_or(anyhow!("No entry {} in the playlist", index))?.clone();
+        let switched = self.source.set_location(&location, self.pipeline.as_ref())?;
+        if !switched {
+            if let Some(pipeline) = self.pipeline.take() {
+                pipeline.set_state(gstreamer::State::Null)?;
+            }
+            self.bus = None;
+        }
+        self.track_selector = None;
+        self.segment_armed = false;
+        {
+            let mut status = self.status.lock().unwrap();
+            status.position = None;
+            status.duration = None;
+            status.tracks.clear();
+            status.decoders.clear();
+        }
+        self.subtitles.lock().unwrap().clear_embedded();
+        self.load_sidecar(&to_path(&location));
+        if switched {
+            self.read_variants();
+        } else {
+            self.launch()?;
+        }
+        if let Some(pipeline) = &self.pipeline {
+            pipeline.set_state(gstreamer::State::Playing)?;
+        }
+        Log::info(format!("{} plays {}/{} : {}", self.id, index + 1, self.playlist.len(), location));
+        Ok(())
+    }
+
+    /// Handle the next message of the bus if any (1ms at most). true if there was one.
+    pub fn check_bus(&mut self) -> bool {
+        let Some(msg) = self.bus.as_ref().and_then(|bus| {
+            bus.timed_pop_filtered(
+                ClockTime::from_mseconds(1), //ClockTime::MAX,
+                &[
+                    MessageType::Error,
+                    MessageType::Eos,
+                    MessageType::StateChanged,
+                    MessageType::Buffering,
+                    MessageType::DurationChanged,
+                    MessageType::AsyncDone,
+                    MessageType::SegmentDone,
+                    MessageType::StreamCollection,
+                    MessageType::StreamsSelected,
+                    MessageType::Element,
+                ],
+            )
+        }) else {
+            return false;
+        };
+        use gstreamer::MessageView;
+
+        match msg.view() {
+            MessageView::Eos(..) => {
+                if let Some(element) = msg.src() {
+                    if let Some(pipeline) = &self.pipeline {
+                        if element == pipeline {
+                            Log::diag(format!("EOS on {} !", self.id));
+                            if let Err(err) = self.on_eos() {
+                                Log::err(format!("Error on {} after EOS : {:?}", self.id, err));
+                            }
+                        }
+                    }
+                }
+            }
+            MessageView::Error(err) => {
+                if let Some(element) = msg.src() {
+                    if let Some(pipeline) = &self.pipeline {
+                        Log::err(format!("Error on {} : {:?} -> {:?}", self.id, element.name(), err.message()));
+                        self.status.lock().unwrap().error = Some(err.error().to_string());
+                        let (factory, negotiation) = error_origin(err);
+                        if self.source.on_error(factory.as_deref(), negotiation) {
+                            self.schedule_reconnect(0.0);
+                        } else if let Some(delay) = self.source.reconnect_delay() {
+                            self.schedule_reconnect(delay);
+                        } else {
+                            if element == pipeline {
+                                pipeline
+                                    .set_state(gstreamer::State::Null)
+                                    .expect("Unable to set the pipeline to the `Null` state");
+
+                                self.bus = None;
+                                self.pipeline = None;
+                                self.track_selector = None;
+                            }
+                            self.status.lock().unwrap().state = StreamState::Error;
+                        }
+                    }
+                }
+            }
+            MessageView::StateChanged(s) => {
+                if let Some(element) = msg.src() {
+                    if let Some(pipeline) = &self.pipeline {
+                        if element == pipeline {
+                            if s.current() == gstreamer::State::Playing {
+                                Log::info(format!("PLAYING {} !", self.id));
+                            } else {
+                                Log::diag(format!("{:?} on {} !", s.current(), self.id));
+                            }
+                            let mut status = self.status.lock().unwrap();
+                            if status.state != StreamState::Buffering {
+                                status.state = match (s.old(), s.current()) {
+                                    (_, gstreamer::State::Playing) => StreamState::Playing,
+                                    (gstreamer::State::Ready, gstreamer::State::Paused) => StreamState::Prerolled,
+                                    (_, gstreamer::State::Paused) => StreamState::Paused,
+                                    _ => status.state,
+                                };
+                            }
+                        }
+                    }
+                }
+            }
+            MessageView::Buffering(buffering) => {
+                let percent = buffering.percent();
+                let mut status = self.status.lock().unwrap();
+                status.buffering = percent;
+                if percent < 100 {
+                    status.state = StreamState::Buffering;
+                } else if status.state == StreamState::Buffering {
+                    status.state = match self.pipeline.as_ref().map(|p| p.current_state()) {
+                        Some(gstreamer::State::Playing) => StreamState::Playing,
+                        _ => StreamState::Paused,
+                    };
+                }
+            }
+            MessageView::AsyncDone(..) => {
+                // the first preroll of a looping media starts the segment seeks
+                if self.looping && self.playlist.len() <= 1 && !self.segment_armed {
+                    if let Some(pipeline) = &self.pipeline {
+                        self.segment_armed = true;
+                        if let Err(err) = transport::segment_seek(pipeline, self.rate, true) {
+                            Log::warn(format!("{} unable to loop : {:?}", self.id, err));
+                        }
+                    }
+                }
+            }
+            MessageView::SegmentDone(..) => {
+                if let Some(pipeline) = &self.pipeline {
+                    Log::diag(format!("Loop on {} !", self.id));
+                    if let Err(err) = transport::segment_seek(pipeline, self.rate, false) {
+                        Log::warn(format!("{} unable to loop : {:?}", self.id, err));
+                    }
+                }
+            }
+            MessageView::StreamCollection(collection) => {
+                let collection = collection.stream_collection();
+                let new_tracks = tracks::tracks_from_collection(&collection);
+                for track in &new_tracks {
+                    Log::diag(format!("{} track {:?}", self.id, track));
+                }
+                // decodebin3 posts the collection, the source tells who handles select-streams
+                let poster = msg.src().and_then(|src| src.downcast_ref::<Element>().cloned());
+                if let (Some(pipeline), Some(poster)) = (&self.pipeline, poster) {
+                    self.track_selector = Some(self.source.track_selector(pipeline, poster));
+                }
+                let mut status = self.status.lock().unwrap();
+                let selected: Vec<String> =
+                    status.tracks.iter().filter(|track| track.selected).map(|track| track.stream_id.clone()).collect();
+                status.tracks = new_tracks;
+                tracks::set_selected(&mut status.tracks, &selected);
+            }
+            MessageView::StreamsSelected(selected) => {
+                let stream_ids: Vec<String> = selected
+                    .streams()
+                    .into_iter()
+                    .filter_map(|stream| stream.stream_id().map(|id| id.to_string()))
+                    .collect();
+                Log::diag(format!("{} selected tracks {:?}", self.id, stream_ids));
+                tracks::set_selected(&mut self.status.lock().unwrap().tracks, &stream_ids);
+            }
+            MessageView::Element(element) => {
+                let uri = element
+                    .structure()
+                    .filter(|s| s.name() == ADAPTIVE_STATISTICS)
+                    .and_then(|s| s.get::<String>("uri").ok());
+                if let Some(uri) = uri {
+                    let demuxer = msg.src().and_then(|src| src.downcast_ref::<Element>().cloned());
+                    self.on_adaptive_download(&uri, demuxer);
+                }
+            }
+            MessageView::DurationChanged(..) => {
+                // the next query will get the new one
+                self.status.lock().unwrap().duration = None;
+            }
+            _ => (),
+        }
+        true
+    }
+}
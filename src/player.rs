@@ -0,0 +1,558 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Ok;
+use gstreamer::{
+    element_error,
+    glib::ffi::gpointer,
+    prelude::{Cast, ElementExt, GstBinExtManual, GstObjectExt, PadExt},
+    Bin, Element, ElementFactory, GhostPad, Pad,
+};
+use gstreamer_app::{AppSink, AppSinkCallbacks};
+use gstreamer_video::{VideoCapsBuilder, VideoFormat, VideoInfo};
+
+use crate::{
+    audio::{
+        audio_appsink_caps, audio_effects, create_sounds, find_sounds, set_audio_callback, AudioSettings,
+        AudioSettingsHandle, SpeakerLayout, EFFECT_FACTORIES,
+    },
+    decoder::is_negotiation_error,
+    playback::Playback,
+    probe::Requirements,
+    status::{PlaybackStatus, PlaybackStatusHandle},
+    subtitles::{subtitle_transform, text_appsink, Subtitles, SubtitlesHandle},
+    transport::PlayerCommand,
+};
+use stereokit_rust::{
+    event_loop::{IStepper, StepperAction, StepperId},
+    font::Font,
+    material::Material,
+    maths::{Matrix, Quat, Vec2, Vec3},
+    mesh::Mesh,
+    sk::{MainThreadToken, SkInfo},
+    sound::{Sound, SoundInst},
+    system::{Log, Text, TextAlign, TextStyle},
+    tex::{Tex, TexFormat, TexSample, TexType},
+    util::named_colors::{RED, WHITE},
+};
+
+/// How a MediaPlayer gets its media: Video1 builds a pipeline for its VideoType, Video2 uses a playbin.
+/// Playback owns the pipeline once built and follows its bus (transport, playlist, reconnection), MediaPlayer shows it
+/// (texture, sounds, subtitles).
+pub trait SourceStrategy {
+    /// Name of the stepper in the logs
+    const NAME: &'static str;
+
+    /// Build the pipeline. Its sinks must come from `sinks`.
+    fn build(&mut self, sinks: &SinkContext) -> Result<Element, anyhow::Error>;
+
+    /// The location (file path or uri) of the media if any
+    fn location(&self) -> Option<String>;
+
+    /// Play another location (an entry of the playlist). Return true if the running `pipeline` has been switched to
+    /// the new location, false if the pipeline has to be built again.
+    fn set_location(&mut self, location: &str, pipeline: Option<&Element>) -> Result<bool, anyhow::Error>;
+
+    /// The element receiving the select-streams events. `poster` is the element that posted the stream collection.
+    fn track_selector(&self, _pipeline: &Element, poster: Element) -> Element {
+        poster
+    }
+
+    /// Seconds to wait before launching a new pipeline when the source is lost (an error or the end of a live
+    /// stream). None if the source is not reconnected.
+    fn reconnect_delay(&self) -> Option<f64> {
+        None
+    }
+
+    /// Called at each step after the position query to add what the source knows to the status
+    fn update_status(&self, _pipeline: &Element, _status: &mut PlaybackStatus) {}
+
+    /// Called when an element built by the factory `factory` posted an error, `negotiation` if it is a failed
+    /// negotiation (see error_origin()). true if the source changed so that a new pipeline may work (ex: without a
+    /// failing decoder), it is launched at once.
+    fn on_error(&mut self, _factory: Option<&str>, _negotiation: bool) -> bool {
+        false
+    }
+
+    /// Called when the pipeline is closed (stop or reconnection) to release what the source holds for it
+    fn shutdown(&mut self) {}
+
+    /// The elements and decoders build() needs from the registry (see CapabilityReport)
+    fn requirements(&self) -> Requirements {
+        Requirements::default()
+    }
+}
+
+const QUEUE: &str = "queue";
+const APPSINK: &str = "appsink";
+/// The converters in front of the video appsink
+const VIDEO_CONVERTERS: [&str; 2] = ["videoconvert", "videoscale"];
+const AUDIO_CONVERTER: &str = "audioconvert";
+const AUDIO_RESAMPLER: &str = "audioresample";
+
+/// What a SourceStrategy needs to create the sinks of a MediaPlayer. It can be moved into the pad-added callbacks.
+#[derive(Clone)]
+pub struct SinkContext {
+    pub width: i32,
+    pub height: i32,
+    pub auto_size: bool,
+    /// The texture of the screen, None to only count the frames in the status
+    pub tex_id: Option<String>,
+    pub status: PlaybackStatusHandle,
+    pub audio: AudioSettingsHandle,
+    pub sound_ids: Vec<String>,
+    pub speaker_layout: SpeakerLayout,
+    pub subtitles: SubtitlesHandle,
+}
+
+impl SinkContext {
+    /// Sinks without screen nor sounds (no StereoKit call): the frames, their size and the audio samples are only
+    /// counted in the status. The pipelines of the sources can be run and checked this way.
+    pub fn offscreen(width: i32, height: i32, auto_size: bool) -> Self {
+        Self {
+            width,
+            height,
+            auto_size,
+            tex_id: None,
+            status: Arc::new(Mutex::new(PlaybackStatus::default())),
+            audio: Arc::new(Mutex::new(AudioSettings::default())),
+            sound_ids: vec![],
+            speaker_layout: SpeakerLayout::Mono,
+            subtitles: Arc::new(Mutex::new(Subtitles::default())),
+        }
+    }
+
+    /// The factories of the elements the sinks are built with (see probe::Requirements::missing)
+    pub fn factories() -> Vec<&'static str> {
+        let mut factories = vec![QUEUE, APPSINK, AUDIO_CONVERTER, AUDIO_RESAMPLER];
+        factories.extend(VIDEO_CONVERTERS);
+        factories.extend(EFFECT_FACTORIES);
+        factories
+    }
+
+    /// `videoconvert ! videoscale ! appsink`, the appsink uploads the frames to the texture of the screen
+    pub fn video_sink_elements(&self) -> Result<Vec<Element>, anyhow::Error> {
+        let mut elements = VIDEO_CONVERTERS
+            .iter()
+            .map(|factory| ElementFactory::make(factory).build())
+            .collect::<Result<Vec<Element>, _>>()?;
+        let appsink = AppSink::builder().caps(&appsink_video_caps(self.width, self.height, self.auto_size)).build();
+        let video_tex = match &self.tex_id {
+            Some(tex_id) => Some(Tex::find(tex_id)?),
+            None => None,
+        };
+        set_video_callback(appsink.clone(), video_tex, self.status.clone());
+        elements.push(appsink.upcast());
+        Ok(elements)
+    }
+
+    /// `audioconvert ! equalizer ! volume ! audioresample ! appsink`, the appsink feeds the sounds of the screen
+    pub fn audio_sink_elements(&self) -> Result<Vec<Element>, anyhow::Error> {
+        let convert = ElementFactory::make(AUDIO_CONVERTER).build()?;
+        let [equalizer, volume] = audio_effects(&self.audio.lock().unwrap())?;
+        let resample = ElementFactory::make(AUDIO_RESAMPLER).build()?;
+        let appsink = AppSink::builder().caps(&audio_appsink_caps(self.speaker_layout)).build();
+        set_audio_callback(appsink.clone(), find_sounds(&self.sound_ids)?, self.status.clone());
+        Ok(vec![convert, equalizer, volume, resample, appsink.upcast()])
+    }
+
+    /// The appsink of the subtitles
+    pub fn text_sink(&self) -> AppSink {
+        text_appsink(self.subtitles.clone())
+    }
+
+    /// Link a video pad of a demuxer or a decodebin to `queue ! decoders ! (video sink)`
+    pub fn link_video_pad(&self, bin: &Bin, src_pad: &Pad, decoders: Vec<Element>) -> Result<(), anyhow::Error> {
+        let mut elements = decoders;
+        elements.extend(self.video_sink_elements()?);
+        link_pad(bin, src_pad, elements)
+    }
+
+    /// Link an audio pad of a demuxer or a decodebin to `queue ! decoders ! (audio sink)`
+    pub fn link_audio_pad(&self, bin: &Bin, src_pad: &Pad, decoders: Vec<Element>) -> Result<(), anyhow::Error> {
+        let mut elements = decoders;
+        elements.extend(self.audio_sink_elements()?);
+        link_pad(bin, src_pad, elements)
+    }
+
+    /// Link a text/x-raw pad of a demuxer or a decodebin to `queue ! appsink` for the subtitles
+    pub fn link_text_pad(&self, bin: &Bin, src_pad: &Pad) -> Result<(), anyhow::Error> {
+        link_pad(bin, src_pad, vec![self.text_sink().upcast()])
+    }
+}
+
+/// Add the elements to the bin, link them and sync their states with the bin
+pub fn add_chain(bin: &Bin, elements: &[Element]) -> Result<(), anyhow::Error> {
+    bin.add_many(elements)?;
+    Element::link_many(elements)?;
+    for e in elements {
+        e.sync_state_with_parent()?;
+    }
+    Ok(())
+}
+
+/// Add `queue ! elements` to the bin and link `src_pad` to the queue
+pub fn link_pad(bin: &Bin, src_pad: &Pad, elements: Vec<Element>) -> Result<(), anyhow::Error> {
+    let queue = ElementFactory::make(QUEUE).build()?;
+    let mut chain = vec![queue.clone()];
+    chain.extend(elements);
+    add_chain(bin, &chain)?;
+
+    let sink_pad = queue.static_pad("sink").expect("queue has no sinkpad");
+    src_pad.link(&sink_pad)?;
+    Ok(())
+}
+
+/// A bin with a ghost sink pad in front of a chain of elements (the audio-sink or video-sink of a playbin)
+pub fn sink_bin(name: &str, elements: &[Element]) -> Result<Bin, anyhow::Error> {
+    let bin = Bin::with_name(name);
+    add_chain(&bin, elements)?;
+    if let Some(pad) = elements.first().and_then(|first| first.static_pad("sink")) {
+        let ghost_pad = GhostPad::with_target(&pad)?;
+        ghost_pad.set_active(true)?;
+        bin.add_pad(&ghost_pad)?;
+    }
+    Ok(bin)
+}
+
+/// The media type of the caps of a pad (ex: "video/x-vp8", "audio/x-raw")
+pub fn pad_media_type(pad: &Pad) -> Option<String> {
+    pad.current_caps().and_then(|caps| caps.structure(0).map(|s| s.name().to_string()))
+}
+
+/// The video stepper: a screen, its sounds and its subtitles showing the Playback of a SourceStrategy.
+/// See Video1 and Video2.
+pub struct MediaPlayer<S: SourceStrategy> {
+    id: StepperId,
+    sk_info: Option<Rc<RefCell<SkInfo>>>,
+    /// The pipeline of the source, its playlist and its transport
+    pub playback: Playback<S>,
+    pub width: i32,
+    pub height: i32,
+    /// Use the size of the decoded video instead of width/height and resize the screen to its aspect ratio
+    pub auto_size: bool,
+    /// Height of the screen, its width comes from the aspect ratio of the video if auto_size
+    pub screen_height: f32,
+    plane_aspect: f32,
+    /// Size of the video texture, see PlaybackStatus::texture_size
+    texture_size: (u32, u32),
+    pub transform_screen: Matrix,
+    pub plane: Mesh,
+    pub text: String,
+    pub transform: Matrix,
+    pub text_style: Option<TextStyle>,
+    /// Style of the subtitles drawn under the screen
+    pub subtitle_style: Option<TextStyle>,
+    video_material: Material,
+    first: bool,
+    status: PlaybackStatusHandle,
+    /// How the audio channels are placed around the screen
+    pub speaker_layout: SpeakerLayout,
+    sounds: Vec<Sound>,
+    sound_ids: Vec<String>,
+    sound_insts: Vec<SoundInst>,
+}
+
+unsafe impl<S: SourceStrategy> Send for MediaPlayer<S> {}
+
+/// This code may be called in some threads, so no StereoKit code
+impl<S: SourceStrategy + Default> Default for MediaPlayer<S> {
+    fn default() -> Self {
+        Self::with_source(S::default())
+    }
+}
+
+/// All the code here run in the main thread
+impl<S: SourceStrategy + 'static> IStepper for MediaPlayer<S> {
+    fn initialize(&mut self, id: StepperId, sk_info: Rc<RefCell<SkInfo>>) -> bool {
+        self.id = id;
+        self.sk_info = Some(sk_info.clone());
+
+        (self.sounds, self.sound_ids) = create_sounds(&self.id, self.speaker_layout);
+
+        let sinks = self.sink_context();
+        if let Err(error) = self.playback.start(&self.id, sinks) {
+            Log::err(format!("Unable to initialize video : {:?}", error));
+            false
+        } else {
+            true
+        }
+    }
+
+    fn step(&mut self, token: &MainThreadToken) {
+        if self.first && self.playback.pipeline().is_some() {
+            let _res = self.playback.play();
+            self.first = false;
+            let size = Vec2::new(self.screen_height * self.plane_aspect, self.screen_height);
+            self.sound_insts = self
+                .sounds
+                .iter()
+                .enumerate()
+                .map(|(i, sound)| {
+                    sound.play(self.speaker_layout.speaker_position(i, self.transform_screen, size), Some(1.0))
+                })
+                .collect();
+        }
+        self.playback.step();
+
+        for e in token.get_event_report().iter() {
+            if let StepperAction::Event(id, key, value) = e {
+                if *id == self.id {
+                    if let Some(command) = PlayerCommand::from_event(key, value) {
+                        if let Err(err) = self.playback.control(command) {
+                            Log::warn(format!("{} unable to apply {:?} : {:?}", self.id, command, err));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.resize_texture();
+        if self.auto_size {
+            self.resize_plane();
+        }
+
+        self.plane.draw(token, &self.video_material, self.transform_screen, None, None);
+        Text::add_at(token, &self.text, self.transform, self.text_style, None, None, None, None, None, None);
+        self.draw_subtitles(token);
+        self.draw_state_text(token);
+    }
+
+    fn shutdown(&mut self) {
+        // the sounds also play after the end of the stream or while reconnecting (no pipeline)
+        for sound_inst in self.sound_insts.drain(..) {
+            sound_inst.stop()
+        }
+        self.playback.stop();
+        Log::diag(format!("Closing {}/{} !!!", S::NAME, self.id));
+    }
+}
+
+impl<S: SourceStrategy> MediaPlayer<S> {
+    /// Create a player for this source
+    pub fn with_source(source: S) -> Self {
+        let playback = Playback::new(source);
+        let status = playback.status_handle();
+        Self {
+            id: S::NAME.to_string(),
+            sk_info: None,
+            playback,
+            width: 1920,
+            height: 1080,
+            auto_size: false,
+            screen_height: 1.080,
+            plane_aspect: 1920.0 / 1080.0,
+            texture_size: (0, 0),
+            transform_screen: Matrix::tr(&(Vec3::new(0.0, 1.0, -1.5)), &Quat::from_angles(90.0, 0.0, 0.0)),
+            plane: Mesh::generate_plane_up(Vec2::new(1.920, 1.080), None, true),
+            text: S::NAME.to_owned(),
+            transform: Matrix::tr(&(Vec3::new(0.0, 2.0, -2.5)), &Quat::from_angles(0.0, 180.0, 0.0)),
+            text_style: Some(Text::make_style(Font::default(), 0.3, RED)),
+            subtitle_style: Some(Text::make_style(Font::default(), 0.05, WHITE)),
+            video_material: Material::unlit().copy(),
+            first: true,
+            status,
+            speaker_layout: SpeakerLayout::default(),
+            sounds: vec![],
+            sound_ids: vec![],
+            sound_insts: vec![],
+        }
+    }
+
+    /// Draw the subtitles of the current position under the screen
+    fn draw_subtitles(&self, token: &MainThreadToken) {
+        let Some(text) = self.playback.subtitle_text() else {
+            return;
+        };
+        let size = Vec2::new(self.screen_height * self.plane_aspect, self.screen_height);
+        let transform = subtitle_transform(self.transform_screen, size);
+        let align = Some(TextAlign::TopCenter);
+        Text::add_at(token, text, transform, self.subtitle_style, None, align, align, None, None, None);
+    }
+
+    /// Draw over the screen the state of a lost source (see StreamState::screen_text)
+    fn draw_state_text(&self, token: &MainThreadToken) {
+        let Some(text) = self.status.lock().unwrap().state.screen_text() else {
+            return;
+        };
+        // the screen is a plane generated up so y is toward the viewer
+        let position = self.transform_screen.transform_point(Vec3::new(0.0, 0.01, 0.0));
+        let toward_viewer = self.transform_screen.transform_normal(Vec3::Y);
+        let transform = Matrix::tr(&position, &Quat::look_dir(toward_viewer));
+        Text::add_at(token, text, transform, self.subtitle_style, None, None, None, None, None, None);
+    }
+    /// Give the size of the decoded frames to the texture. The appsink drops the frames until then.
+    fn resize_texture(&mut self) {
+        let mut status = self.status.lock().unwrap();
+        let Some(size) = status.video_size.filter(|size| *size != self.texture_size) else {
+            return;
+        };
+        let Result::Ok(mut video_tex) = Tex::find(self.id.clone() + "video") else {
+            return;
+        };
+        let mut black = vec![0u8; size.0 as usize * size.1 as usize * 4];
+        video_tex.set_colors(size.0 as usize, size.1 as usize, black.as_mut_ptr() as gpointer);
+        self.texture_size = size;
+        status.texture_size = Some(size);
+    }
+
+    /// Resize the screen to the display aspect ratio of the video if it changed
+    fn resize_plane(&mut self) {
+        let Some(aspect) = self.status.lock().unwrap().display_aspect else {
+            return;
+        };
+        if (aspect - self.plane_aspect).abs() > 0.001 {
+            self.plane_aspect = aspect;
+            let size = Vec2::new(self.screen_height * aspect, self.screen_height);
+            self.plane = Mesh::generate_plane_up(size, None, true);
+            for (i, sound_inst) in self.sound_insts.iter_mut().enumerate() {
+                sound_inst.position(self.speaker_layout.speaker_position(i, self.transform_screen, size));
+            }
+        }
+    }
+
+    /// A copy of the current playback status
+    pub fn get_status(&self) -> PlaybackStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// The status shared with the stepper. Get it before giving the stepper to StereoKit to follow the playback
+    /// from the application.
+    pub fn status_handle(&self) -> PlaybackStatusHandle {
+        self.status.clone()
+    }
+
+    /// What the source needs to create the sinks, with the texture of the screen. A new entry of the playlist or a
+    /// new pipeline after a reconnection keep them.
+    fn sink_context(&mut self) -> SinkContext {
+        let tex_id = self.id.clone() + "video";
+        if Tex::find(&tex_id).is_err() {
+            let mut video_tex =
+                Tex::gen_color(WHITE, self.width, self.height, TexType::Rendertarget, TexFormat::RGBA32);
+            //let mut video_tex = Tex::render_target(self.width, self.height, None, None, None)?;
+            let material_id = self.id.clone() + "material_video";
+            video_tex.id(&tex_id).sample_mode(TexSample::Point);
+            self.video_material.id(&material_id).diffuse_tex(&video_tex);
+            self.texture_size = (self.width as u32, self.height as u32);
+        }
+        self.status.lock().unwrap().texture_size = Some(self.texture_size);
+        SinkContext {
+            width: self.width,
+            height: self.height,
+            auto_size: self.auto_size,
+            tex_id: Some(tex_id),
+            status: self.status.clone(),
+            audio: self.playback.audio_handle(),
+            sound_ids: self.sound_ids.clone(),
+            speaker_layout: self.speaker_layout,
+            subtitles: self.playback.subtitles_handle(),
+        }
+    }
+}
+
+/// The factory of the element that posted an error and whether the error is a failed negotiation (see
+/// SourceStrategy::on_error)
+pub fn error_origin(err: &gstreamer::message::Error) -> (Option<String>, bool) {
+    let factory = err
+        .src()
+        .and_then(|src| src.downcast_ref::<Element>())
+        .and_then(|element| element.factory())
+        .map(|factory| factory.name().to_string());
+    let debug = err.debug();
+    (factory, is_negotiation_error(&err.error(), debug.as_deref()))
+}
+
+/// Getting data out of the appsink is done by setting callbacks on it.
+/// The appsink will then call those handlers, as soon as data is available.
+/// The size of the samples is reported in the status (with the display aspect ratio), the stepper resizes the
+/// texture from the main thread. Meanwhile the samples of another size than the texture are dropped.
+fn set_video_callback(appsink: AppSink, mut video_tex: Option<Tex>, status: PlaybackStatusHandle) {
+    let mut frame_size = (0, 0);
+    appsink.set_callbacks(
+        AppSinkCallbacks::builder()
+            // Add a handler to the "new-sample" signal.
+            .new_sample(move |appsink| {
+                // Pull the sample in question out of the appsink's buffer.
+                let sample = appsink.pull_sample().map_err(|_| gstreamer::FlowError::Eos)?;
+                let buffer = sample.buffer().ok_or_else(|| {
+                    element_error!(appsink, gstreamer::ResourceError::Failed, ("Failed to get buffer from appsink"));
+
+                    gstreamer::FlowError::Error
+                })?;
+
+                // At this point, buffer is only a reference to an existing memory region somewhere.
+                // When we want to access its content, we have to map it while requesting the required
+                // mode of access (read, read/write).
+                // This type of abstraction is necessary, because the buffer in question might not be
+                // on the machine's main memory itself, but rather in the GPU's memory.
+                // So mapping the buffer makes the underlying memory region accessible to us.
+                // See: https://gstreamer.freedesktop.org/documentation/plugin-development/advanced/allocation.html
+                let map = buffer.map_readable().map_err(|_| {
+                    element_error!(appsink, gstreamer::ResourceError::Failed, ("Failed to map Audio buffer readable"));
+
+                    gstreamer::FlowError::Error
+                })?;
+
+                let info = sample.caps().and_then(|caps| VideoInfo::from_caps(caps).ok()).ok_or_else(|| {
+                    element_error!(
+                        appsink,
+                        gstreamer::CoreError::Negotiation,
+                        ("Failed to get video info from sample caps")
+                    );
+
+                    gstreamer::FlowError::NotNegotiated
+                })?;
+                let size = (info.width(), info.height());
+                // the texture is only uploaded here, it is resized by the stepper
+                let mut status = status.lock().unwrap();
+                if size != frame_size {
+                    frame_size = size;
+                    let par = info.par();
+                    let aspect = (info.width() as f32 * par.numer() as f32)
+                        / (info.height() as f32 * par.denom() as f32).max(1.0);
+                    Log::info(format!("Video frames {}x{} (display aspect {:.3})", size.0, size.1, aspect));
+                    status.video_size = Some(size);
+                    status.display_aspect = Some(aspect);
+                }
+                if let Some(video_tex) = video_tex.as_mut() {
+                    if status.texture_size != Some(size) {
+                        // dropped until the texture has the new size
+                        return Result::<gstreamer::FlowSuccess, gstreamer::FlowError>::Ok(gstreamer::FlowSuccess::Ok);
+                    }
+                    video_tex.set_colors(size.0 as usize, size.1 as usize, map.as_ptr() as gpointer);
+                }
+                status.frames += 1;
+
+                Result::<gstreamer::FlowSuccess, gstreamer::FlowError>::Ok(gstreamer::FlowSuccess::Ok)
+            })
+            .new_preroll(|_appsink| {
+                Log::diag(">>>>>new_preroll");
+                Result::<gstreamer::FlowSuccess, gstreamer::FlowError>::Ok(gstreamer::FlowSuccess::Ok)
+            })
+            .propose_allocation(|_appsink, allocation| {
+                Log::diag(format!(">>>>>propose_allocation {:?}", allocation));
+                true
+            })
+            .eos(|_appsink| {
+                Log::diag(">>>>>eos");
+            })
+            .build(),
+    );
+}
+
+/// The caps of the video appsinks. With auto_size only the format is fixed so the size of the decoder is kept.
+pub(crate) fn appsink_video_caps(width: i32, height: i32, auto_size: bool) -> gstreamer::Caps {
+    let builder = VideoCapsBuilder::new().format(VideoFormat::Rgba);
+    let builder = if auto_size { builder } else { builder.width(width).height(height) };
+    if cfg!(target_os = "android") {
+        builder
+            .features([gstreamer_gl::CAPS_FEATURE_MEMORY_GL_MEMORY])
+            .field("texture-target", "external-oes")
+            .build()
+    } else {
+        builder.build()
+    }
+}
@@ -0,0 +1,77 @@
+use std::path::Path;
+
+use anyhow::Ok;
+
+/// An entry of a M3U/M3U8 playlist
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaylistEntry {
+    /// An uri (file:, https:, rtsp:, ...) or an absolute file path
+    pub location: String,
+    /// The title of the #EXTINF line if any
+    pub title: Option<String>,
+    /// The duration in seconds of the #EXTINF line if any (-1 for a live stream)
+    pub duration: Option<f64>,
+}
+
+/// Read a local M3U or M3U8 playlist. Relative paths are resolved from the directory of the playlist.
+pub fn parse_m3u_file(path: impl AsRef<Path>) -> Result<Vec<PlaylistEntry>, anyhow::Error> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)?;
+    // .m3u8 are utf-8, old .m3u may not be so we don't fail on a bad char
+    let content = String::from_utf8_lossy(&bytes);
+    Ok(parse_m3u(&content, path.parent()))
+}
+
+/// Parse the content of a M3U/M3U8 playlist. `base_dir` is used to resolve the relative paths.
+pub fn parse_m3u(content: &str, base_dir: Option<&Path>) -> Vec<PlaylistEntry> {
+    let mut entries = vec![];
+    let mut title = None;
+    let mut duration = None;
+    for line in content.trim_start_matches('\u{feff}').lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            // #EXTINF:<duration> [attributes],<title>
+            let (head, name) = info.split_once(',').unwrap_or((info, ""));
+            duration = head.split_whitespace().next().and_then(|d| d.parse::<f64>().ok());
+            title = if name.trim().is_empty() { None } else { Some(name.trim().to_string()) };
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        let location = if is_uri(line) || Path::new(line).is_absolute() {
+            line.to_string()
+        } else if let Some(base_dir) = base_dir {
+            base_dir.join(line).to_string_lossy().into_owned()
+        } else {
+            line.to_string()
+        };
+        entries.push(PlaylistEntry { location, title: title.take(), duration: duration.take() });
+    }
+    entries
+}
+
+/// Is it an uri rather than a file path
+pub fn is_uri(location: &str) -> bool {
+    location.starts_with("file:") || location.contains("://")
+}
+
+/// The uri of a playlist location (for uridecodebin/playbin)
+pub fn to_uri(location: &str) -> String {
+    if is_uri(location) {
+        location.to_string()
+    } else {
+        format!("file:{}", location)
+    }
+}
+
+/// The file path of a playlist location (for filesrc)
+pub fn to_path(location: &str) -> String {
+    match location.strip_prefix("file://").or_else(|| location.strip_prefix("file:")) {
+        Some(path) => path.to_string(),
+        None => location.to_string(),
+    }
+}
@@ -0,0 +1,352 @@
+use anyhow::{anyhow, Ok};
+use gstreamer::{
+    glib::translate::{FromGlib, IntoGlib},
+    prelude::{GstObjectExt, PluginFeatureExtManual},
+    Caps, ElementFactory, Rank, Registry,
+};
+
+use crate::{
+    capture::CaptureTarget,
+    decoder::{DecoderCandidate, DecoderResolver},
+    player::{SinkContext, SourceStrategy},
+    rtp::RtpSettings,
+    rtsp::RtspTransport,
+    video1::VideoType,
+    video2::PlaybinSource,
+};
+#[cfg(target_os = "android")]
+use stereokit_rust::system::Log;
+
+/// The codecs probed at startup with the media type of their decoders
+pub const PROBED_CODECS: [(&str, &str); 9] = [
+    ("H264", "video/x-h264"),
+    ("H265", "video/x-h265"),
+    ("VP8", "video/x-vp8"),
+    ("VP9", "video/x-vp9"),
+    ("AV1", "video/x-av1"),
+    ("Opus", "audio/x-opus"),
+    ("Vorbis", "audio/x-vorbis"),
+    ("AAC", "audio/mpeg, mpegversion=(int)4"),
+    ("MP3", "audio/mpeg, mpegversion=(int)1"),
+];
+
+/// The ranks given on Android: the MediaCodec decoders of the Quest (amcviddec-*) and the software decoders that
+/// were tested are preferred to the other ones
+pub const ANDROID_RANK_OVERRIDES: &str = "\
+openh264dec primary
+vp8dec primary
+vp9dec primary
+gldownload primary
+amcviddec-c2qtiavcdecoder primary
+amcviddec-omxqcomvideodecoderh263 primary
+amcviddec-omxqcomvideodecoderavc primary
+amcviddec-omxqcomvideodecoderhevc primary
+amcviddec-omxqcomvideodecodermpeg2 primary
+amcviddec-omxqcomvideodecodermpeg4 primary
+amcviddec-omxqcomvideodecodervp8 primary
+amcviddec-omxqcomvideodecodervp9 primary
+";
+
+/// What a source needs from the registry to be launched
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Requirements {
+    /// Factory names (ex: rtspsrc)
+    pub elements: Vec<&'static str>,
+    /// Caps of the streams decoded with a DecoderResolver (ex: video/x-vp8)
+    pub decoded: Vec<&'static str>,
+    /// Caps of the streams decoded if the media has them (ex: the audio of a file). The source plays without them.
+    pub optional: Vec<&'static str>,
+}
+
+impl Requirements {
+    pub fn new(elements: &[&'static str], decoded: &[&'static str]) -> Self {
+        Self { elements: elements.to_vec(), decoded: decoded.to_vec(), optional: vec![] }
+    }
+
+    /// The same requirements with these optional decoded streams
+    pub fn with_optional(mut self, optional: &[&'static str]) -> Self {
+        self.optional = optional.to_vec();
+        self
+    }
+
+    /// The missing elements and the streams without a decoder (ex: "webrtcbin", "decoder of video/x-vp9"), empty if
+    /// the source can be launched. The sink elements are checked too.
+    pub fn missing(&self, decoders: &DecoderResolver) -> Vec<String> {
+        let mut missing: Vec<String> = SinkContext::factories()
+            .iter()
+            .chain(self.elements.iter())
+            .filter(|name| ElementFactory::find(name).is_none())
+            .map(|name| name.to_string())
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing.extend(missing_decoders(&self.decoded, decoders));
+        missing
+    }
+
+    /// The optional streams without a decoder (ex: "decoder of audio/x-vorbis"), they won't be played
+    pub fn missing_optional(&self, decoders: &DecoderResolver) -> Vec<String> {
+        missing_decoders(&self.optional, decoders)
+    }
+}
+
+fn missing_decoders(caps: &[&str], decoders: &DecoderResolver) -> Vec<String> {
+    caps.iter()
+        .filter(|caps| caps.parse::<Caps>().map(|caps| decoders.candidates(&caps).is_empty()).unwrap_or(true))
+        .map(|caps| format!("decoder of {}", caps))
+        .collect()
+}
+
+/// The requirements of each VideoType of Video1 (RtpStream with its default H264 codec) and of the playbins of Video2
+pub fn source_requirements() -> Vec<(&'static str, Requirements)> {
+    let rtp = RtpSettings::default();
+    let video_types = [
+        ("RtpStream", VideoType::RtpStream { port: 0 }),
+        ("RtpRawStream", VideoType::RtpRawStream { port: 0 }),
+        ("Decodebin", VideoType::Decodebin { uri: "file:".into(), v3_enabled: false }),
+        ("Decodebin3", VideoType::Decodebin { uri: "file:".into(), v3_enabled: true }),
+        ("H264File", VideoType::H264File { uri: String::new() }),
+        ("VP8File", VideoType::VP8File { uri: String::new() }),
+        ("VP9File", VideoType::VP9File { uri: String::new() }),
+        ("Rtsp", VideoType::Rtsp { url: String::new(), latency: 0, transport: RtspTransport::Auto }),
+        ("Srt", VideoType::Srt { uri: String::new(), latency: 0, passphrase: None }),
+        ("Whep", VideoType::Whep { url: String::new(), token: None }),
+        ("Device", VideoType::Device { camera: None, mode: None, microphone: None }),
+        (
+            "ScreenCapture",
+            VideoType::ScreenCapture { target: CaptureTarget::default(), cursor: true, max_fps: 0 },
+        ),
+        (
+            "ScreenCapture PipeWire",
+            VideoType::ScreenCapture { target: CaptureTarget::PipeWire(None), cursor: true, max_fps: 0 },
+        ),
+        ("TestPattern", VideoType::TestPattern { pattern: "smpte".into(), tone: Some("sine".into()) }),
+    ];
+    let mut sources: Vec<(&str, Requirements)> =
+        video_types.iter().map(|(name, video_type)| (*name, video_type.requirements(&rtp))).collect();
+    for v3_enabled in [false, true] {
+        let name = if v3_enabled { "Playbin3" } else { "Playbin" };
+        sources.push((name, PlaybinSource { uri: String::new(), v3_enabled }.requirements()));
+    }
+    sources
+}
+
+/// A new rank for the factory of an element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankOverride {
+    pub factory: String,
+    pub rank: i32,
+}
+
+/// Parse a rank: a number or none, marginal, secondary, primary with an optional offset (ex: primary+1)
+pub fn parse_rank(text: &str) -> Result<i32, anyhow::Error> {
+    let text = text.trim().to_lowercase();
+    if let Result::Ok(rank) = text.parse::<i32>() {
+        return Ok(rank);
+    }
+    let (name, offset) = match text.find(['+', '-']) {
+        Some(pos) => (&text[..pos], text[pos..].parse::<i32>().map_err(|_| anyhow!("Invalid rank {}", text))?),
+        None => (text.as_str(), 0),
+    };
+    let rank = match name {
+        "none" => Rank::NONE,
+        "marginal" => Rank::MARGINAL,
+        "secondary" => Rank::SECONDARY,
+        "primary" => Rank::PRIMARY,
+        _ => return Err(anyhow!("Invalid rank {} (a number or none, marginal, secondary, primary)", text)),
+    };
+    Ok(rank.into_glib() + offset)
+}
+
+/// Parse the lines `<factory> <rank>` of a rank file (ex: `vah264dec primary+1`, `avdec_h264 0`). Empty lines and
+/// the ones starting with # are ignored.
+pub fn parse_rank_overrides(text: &str) -> Result<Vec<RankOverride>, anyhow::Error> {
+    let mut overrides = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((factory, rank)) = line.split_once(char::is_whitespace) else {
+            return Err(anyhow!("Line {} : <factory> <rank> expected instead of {}", number + 1, line));
+        };
+        let rank = parse_rank(rank).map_err(|err| anyhow!("Line {} : {}", number + 1, err))?;
+        overrides.push(RankOverride { factory: factory.to_string(), rank });
+    }
+    Ok(overrides)
+}
+
+/// Give their new rank to the features of the registry. Return the factories that don't exist.
+pub fn apply_rank_overrides(overrides: &[RankOverride]) -> Vec<String> {
+    let registry = Registry::get();
+    let mut unknown = vec![];
+    for rank_override in overrides {
+        match registry.lookup_feature(&rank_override.factory) {
+            // any i32 is a valid GstRank
+            Some(feature) => feature.set_rank(unsafe { Rank::from_glib(rank_override.rank) }),
+            None => unknown.push(rank_override.factory.clone()),
+        }
+    }
+    unknown
+}
+
+/// Init GStreamer. On Android, the MediaCodec decoders found for the main codecs are logged and ranked before the
+/// software ones (see ANDROID_RANK_OVERRIDES).
+pub fn gstreamer_init() -> Result<(), anyhow::Error> {
+    #[cfg(not(target_os = "android"))]
+    {
+        gstreamer::init()?;
+    }
+    #[cfg(target_os = "android")]
+    {
+        gstreamer::log::set_default_threshold(gstreamer::DebugLevel::Debug);
+    }
+
+    #[cfg(target_os = "android")]
+    {
+        let ctx = ndk_context::android_context();
+        let vm = unsafe { jni::JavaVM::from_raw(ctx.vm() as _) }?;
+        let mut env = vm.attach_current_thread()?;
+
+        let media_codec_list = env.new_object("android/media/MediaCodecList", "(I)V", &[0i32.into()])?;
+
+        let omx_decode_list = vec!["video/avc", "video/hevc", "video/x-vnd.on2.vp8", "video/x-vnd.on2.vp9"];
+
+        for str in omx_decode_list {
+            let jstr = env.new_string(str)?;
+            let video_format = env.call_static_method(
+                "android/media/MediaFormat",
+                "createVideoFormat",
+                "(Ljava/lang/String;II)Landroid/media/MediaFormat;",
+                &[(&jstr).into(), 800i32.into(), 600i32.into()],
+            )?;
+
+            let media_codec: jni::objects::JString = env
+                .call_method(
+                    &media_codec_list,
+                    "findDecoderForFormat",
+                    "(Landroid/media/MediaFormat;)Ljava/lang/String;",
+                    &[video_format.borrow()],
+                )?
+                .l()?
+                .into();
+
+            // "OMX.qcom.video.decoder.avc",
+            // "OMX.qcom.video.decoder.vp8",
+            // "OMX.qcom.video.decoder.hevc",
+            match env.get_string(&media_codec) {
+                Result::Ok(codec) => {
+                    let str_codec: String = codec.into();
+                    Log::diag(format!("Codec for {} -> {}", str, str_codec));
+                }
+                Err(err) => {
+                    Log::warn(format!("No codec for {} ->  {:?}", str, err));
+                }
+            };
+        }
+
+        // the MediaCodec decoders before the software ones
+        for factory in apply_rank_overrides(&parse_rank_overrides(ANDROID_RANK_OVERRIDES)?) {
+            Log::warn(format!("Feature {} does not exist !", factory));
+        }
+    }
+    Ok(())
+}
+
+/// The decoders of a codec in the order a DecoderResolver tries them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodecSupport {
+    pub codec: &'static str,
+    pub decoders: Vec<DecoderCandidate>,
+}
+
+impl CodecSupport {
+    /// ex: "H264 : vah264dec (hardware, 257), avdec_h264 (256)" or "AV1 : no decoder"
+    pub fn label(&self) -> String {
+        if self.decoders.is_empty() {
+            return format!("{} : no decoder", self.codec);
+        }
+        let decoders: Vec<String> = self
+            .decoders
+            .iter()
+            .map(|decoder| {
+                if decoder.hardware {
+                    format!("{} (hardware, {})", decoder.name, decoder.rank)
+                } else {
+                    format!("{} ({})", decoder.name, decoder.rank)
+                }
+            })
+            .collect();
+        format!("{} : {}", self.codec, decoders.join(", "))
+    }
+}
+
+/// Can a source be launched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSupport {
+    pub name: String,
+    /// See Requirements::missing
+    pub missing: Vec<String>,
+    /// See Requirements::missing_optional
+    pub without: Vec<String>,
+}
+
+impl SourceSupport {
+    pub fn launchable(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    /// ex: "Rtsp : ok", "H264File : ok without decoder of audio/mpeg, mpegversion=(int)4" or
+    /// "Whep : missing webrtcbin"
+    pub fn label(&self) -> String {
+        if !self.launchable() {
+            format!("{} : missing {}", self.name, self.missing.join(", "))
+        } else if self.without.is_empty() {
+            format!("{} : ok", self.name)
+        } else {
+            format!("{} : ok without {}", self.name, self.without.join(", "))
+        }
+    }
+}
+
+/// What the GStreamer registry offers to the screens
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapabilityReport {
+    /// Names of the loaded plugins, sorted
+    pub plugins: Vec<String>,
+    pub codecs: Vec<CodecSupport>,
+    pub sources: Vec<SourceSupport>,
+}
+
+impl CapabilityReport {
+    /// Probe the registry for the PROBED_CODECS (decoders in the order of `decoders`) and the `sources`
+    pub fn probe(decoders: &DecoderResolver, sources: &[(&str, Requirements)]) -> Self {
+        let mut plugins: Vec<String> =
+            Registry::get().plugins().iter().map(|plugin| plugin.plugin_name().to_string()).collect();
+        plugins.sort();
+        let codecs = PROBED_CODECS
+            .iter()
+            .map(|&(codec, caps)| CodecSupport {
+                codec,
+                decoders: caps.parse::<Caps>().map(|caps| decoders.candidates(&caps)).unwrap_or_default(),
+            })
+            .collect();
+        let sources = sources
+            .iter()
+            .map(|(name, requirements)| SourceSupport {
+                name: name.to_string(),
+                missing: requirements.missing(decoders),
+                without: requirements.missing_optional(decoders),
+            })
+            .collect();
+        Self { plugins, codecs, sources }
+    }
+
+    /// The report as text lines (the plugins on one line)
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{} plugins : {}", self.plugins.len(), self.plugins.join(", "))];
+        lines.extend(self.codecs.iter().map(CodecSupport::label));
+        lines.extend(self.sources.iter().map(SourceSupport::label));
+        lines
+    }
+}
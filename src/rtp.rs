@@ -0,0 +1,405 @@
+use std::{
+    net::IpAddr,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Ok};
+use gstreamer::{
+    prelude::{Cast, ElementExt, GstBinExt, GstObjectExt, ObjectExt, PadExtManual},
+    Bin, Caps, Element, ElementFactory, PadProbeReturn, PadProbeType, Structure,
+};
+use gstreamer_net::{
+    gio::{prelude::InetAddressExt, InetSocketAddress},
+    NetAddressMeta,
+};
+use gstreamer_sdp::{SDPMediaRef, SDPMessage};
+
+use crate::decoder::{DecoderCandidate, DecoderResolver};
+
+/// Name of the rtpjitterbuffer of a RtpStream (see rtp_stats())
+pub const RTP_JITTERBUFFER: &str = "jitterbuffer";
+
+/// The video codecs of RtpStream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RtpCodec {
+    #[default]
+    H264,
+    H265,
+    VP8,
+    VP9,
+    AV1,
+    /// Uncompressed video (RFC 4175)
+    Raw,
+}
+
+impl RtpCodec {
+    /// The encoding-name of the rtp caps (rtpmap of a SDP)
+    pub fn encoding_name(&self) -> &'static str {
+        match self {
+            RtpCodec::H264 => "H264",
+            RtpCodec::H265 => "H265",
+            RtpCodec::VP8 => "VP8",
+            RtpCodec::VP9 => "VP9",
+            RtpCodec::AV1 => "AV1",
+            RtpCodec::Raw => "RAW",
+        }
+    }
+
+    pub fn from_encoding_name(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "H264" => Some(RtpCodec::H264),
+            "H265" => Some(RtpCodec::H265),
+            "VP8" => Some(RtpCodec::VP8),
+            "VP9" => Some(RtpCodec::VP9),
+            "AV1" => Some(RtpCodec::AV1),
+            "RAW" => Some(RtpCodec::Raw),
+            _ => None,
+        }
+    }
+
+    pub fn depayloader(&self) -> &'static str {
+        match self {
+            RtpCodec::H264 => "rtph264depay",
+            RtpCodec::H265 => "rtph265depay",
+            RtpCodec::VP8 => "rtpvp8depay",
+            RtpCodec::VP9 => "rtpvp9depay",
+            RtpCodec::AV1 => "rtpav1depay",
+            RtpCodec::Raw => "rtpvrawdepay",
+        }
+    }
+
+    /// The parser between the depayloader and the decoder if the codec needs one
+    pub fn parser(&self) -> Option<&'static str> {
+        match self {
+            RtpCodec::H264 => Some("h264parse"),
+            RtpCodec::H265 => Some("h265parse"),
+            RtpCodec::AV1 => Some("av1parse"),
+            _ => None,
+        }
+    }
+
+    /// The media type of the depayloaded stream, the decoder is chosen for it. None for raw video.
+    pub fn media_type(&self) -> Option<&'static str> {
+        match self {
+            RtpCodec::H264 => Some("video/x-h264"),
+            RtpCodec::H265 => Some("video/x-h265"),
+            RtpCodec::VP8 => Some("video/x-vp8"),
+            RtpCodec::VP9 => Some("video/x-vp9"),
+            RtpCodec::AV1 => Some("video/x-av1"),
+            RtpCodec::Raw => None,
+        }
+    }
+}
+
+/// How to receive a RtpStream
+#[derive(Debug, Clone, PartialEq)]
+pub struct RtpSettings {
+    pub codec: RtpCodec,
+    /// Dynamic payload type (96-127) of the sender
+    pub payload: i32,
+    pub clock_rate: i32,
+    /// Latency in ms of the jitterbuffer: how long reordered packets are waited for
+    pub latency: u32,
+    /// Drop the packets arriving later than the latency instead of letting the latency grow
+    pub drop_on_latency: bool,
+    /// More fields of the rtp caps: the fmtp parameters of a SDP (sprop-parameter-sets, sampling, width, ...)
+    pub parameters: Vec<(String, String)>,
+}
+
+impl Default for RtpSettings {
+    fn default() -> Self {
+        Self {
+            codec: RtpCodec::H264,
+            payload: 96,
+            clock_rate: 90000,
+            latency: 200,
+            drop_on_latency: false,
+            parameters: vec![],
+        }
+    }
+}
+
+impl RtpSettings {
+    /// The caps of the udpsrc
+    pub fn caps(&self) -> gstreamer::Caps {
+        let mut builder = gstreamer::Caps::builder("application/x-rtp")
+            .field("media", "video")
+            .field("clock-rate", self.clock_rate)
+            .field("encoding-name", self.codec.encoding_name());
+        for (name, value) in &self.parameters {
+            builder = builder.field(name.as_str(), value.as_str());
+        }
+        builder.field("payload", self.payload).build()
+    }
+
+    /// A parameter given by the sender (see parameters)
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// The RFC 4175 parameters of an uncompressed stream. All the values are strings.
+pub fn raw_parameters(width: i32, height: i32, sampling: &str, framerate: i32) -> Vec<(String, String)> {
+    vec![
+        ("sampling".into(), sampling.into()),
+        ("depth".into(), "8".into()),
+        ("width".into(), width.to_string()),
+        ("height".into(), height.to_string()),
+        ("colorimetry".into(), "SMPTE240M".into()),
+        ("a-framerate".into(), framerate.to_string()),
+    ]
+}
+
+/// `rtpjitterbuffer ! depayloader ! parser ! decoder` for these settings (no parser nor decoder for raw video), the
+/// decoder is chosen by `decoders`
+pub fn rtp_decode_elements(
+    settings: &RtpSettings,
+    decoders: &DecoderResolver,
+) -> Result<(Vec<Element>, Option<DecoderCandidate>), anyhow::Error> {
+    let jitterbuffer = ElementFactory::make("rtpjitterbuffer")
+        .name(RTP_JITTERBUFFER)
+        .property("latency", settings.latency)
+        .property("drop-on-latency", settings.drop_on_latency)
+        .build()?;
+    let mut elements = vec![jitterbuffer];
+    elements.push(ElementFactory::make(settings.codec.depayloader()).build()?);
+    if let Some(parser) = settings.codec.parser() {
+        elements.push(ElementFactory::make(parser).build()?);
+    }
+    let mut chosen = None;
+    if let Some(media_type) = settings.codec.media_type() {
+        let (decoder, candidate) = decoders.resolve_media_type(media_type)?;
+        elements.push(decoder);
+        chosen = Some(candidate);
+    }
+    Ok((elements, chosen))
+}
+
+/// Where and from whom the packets of a RtpStream are received
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UdpSettings {
+    /// Multicast group to join or local address to listen on, all the interfaces if None
+    pub address: Option<String>,
+    /// Interfaces joining the multicast group (ex: "eth0" or "eth0,wlan0"), the one of the default route if None
+    pub multicast_iface: Option<String>,
+    /// Only the packets of these senders are received (all if empty). The kernel filters them for a multicast group
+    /// (IGMPv3 source-specific multicast).
+    pub sources: Vec<String>,
+    /// Size in bytes of the receive buffer of the socket, the OS default if None
+    pub buffer_size: Option<i32>,
+}
+
+impl UdpSettings {
+    /// Is the address a multicast group
+    pub fn is_multicast(&self) -> bool {
+        self.address
+            .as_deref()
+            .and_then(|address| address.parse::<IpAddr>().ok())
+            .is_some_and(|ip| ip.is_multicast())
+    }
+}
+
+/// Create a udpsrc receiving these caps on `port`. The packets of the senders not in `udp.sources` are dropped.
+pub fn udp_source(port: i32, udp: &UdpSettings, caps: &Caps) -> Result<Element, anyhow::Error> {
+    let mut builder = ElementFactory::make("udpsrc").property("port", port).property("caps", caps);
+    if let Some(address) = &udp.address {
+        builder = builder.property("address", address);
+    }
+    if let Some(multicast_iface) = &udp.multicast_iface {
+        builder = builder.property("multicast-iface", multicast_iface);
+    }
+    if let Some(buffer_size) = udp.buffer_size {
+        builder = builder.property("buffer-size", buffer_size);
+    }
+    let udpsrc = builder.build()?;
+    if udp.sources.is_empty() {
+        return Ok(udpsrc);
+    }
+
+    let sources = udp
+        .sources
+        .iter()
+        .map(|source| source.parse::<IpAddr>().map_err(|err| anyhow!("Invalid source address {} : {}", source, err)))
+        .collect::<Result<Vec<IpAddr>, anyhow::Error>>()?;
+    // GStreamer 1.24 and later
+    if udp.is_multicast() && udpsrc.find_property("multicast-source").is_some() {
+        let multicast_source: String = sources.iter().map(|source| format!("+{}", source)).collect();
+        udpsrc.set_property("multicast-source", multicast_source);
+    }
+    // a unicast port or an older udpsrc, the sender address is in the meta of the buffers
+    let src_pad = udpsrc.static_pad("src").ok_or(anyhow!("udpsrc has no src pad"))?;
+    src_pad.add_probe(PadProbeType::BUFFER, move |_, info| {
+        let sender = info
+            .buffer()
+            .and_then(|buffer| buffer.meta::<NetAddressMeta>())
+            .and_then(|meta| meta.addr().downcast::<InetSocketAddress>().ok())
+            .and_then(|addr| addr.address().to_str().parse::<IpAddr>().ok());
+        match sender {
+            Some(sender) if !sources.contains(&sender) => PadProbeReturn::Drop,
+            _ => PadProbeReturn::Ok,
+        }
+    });
+    Ok(udpsrc)
+}
+
+/// The packet statistics of the jitterbuffer of a RtpStream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RtpStats {
+    /// Packets given to the depayloader
+    pub pushed: u64,
+    /// Packets never received (gaps in the sequence numbers)
+    pub lost: u64,
+    /// Packets received after the latency and dropped
+    pub late: u64,
+    pub duplicates: u64,
+    /// Average jitter in ns
+    pub avg_jitter: u64,
+}
+
+impl RtpStats {
+    /// Read the `stats` structure of a rtpjitterbuffer
+    pub fn from_structure(stats: &Structure) -> Self {
+        let get = |field: &str| stats.get::<u64>(field).unwrap_or(0);
+        Self {
+            pushed: get("num-pushed"),
+            lost: get("num-lost"),
+            late: get("num-late"),
+            duplicates: get("num-duplicates"),
+            avg_jitter: get("avg-jitter"),
+        }
+    }
+
+    /// Lost packets in % of the expected ones
+    pub fn loss_percent(&self) -> f32 {
+        let expected = self.pushed + self.lost;
+        if expected == 0 {
+            0.0
+        } else {
+            self.lost as f32 * 100.0 / expected as f32
+        }
+    }
+}
+
+/// The statistics of the jitterbuffer of this pipeline, None if there is none
+pub fn rtp_stats(pipeline: &Element) -> Option<RtpStats> {
+    let jitterbuffer = pipeline.downcast_ref::<Bin>()?.by_name(RTP_JITTERBUFFER)?;
+    Some(RtpStats::from_structure(&jitterbuffer.property::<Structure>("stats")))
+}
+
+/// Follows the packets going out of an element (the udpsrc) to detect a lost signal
+#[derive(Debug, Clone)]
+pub struct PacketWatch {
+    last_packet: Arc<Mutex<Instant>>,
+}
+
+impl PacketWatch {
+    /// Watch the src pad of `element`. Until the first packet the silence is counted from now.
+    pub fn new(element: &Element) -> Result<Self, anyhow::Error> {
+        let last_packet = Arc::new(Mutex::new(Instant::now()));
+        let src_pad = element.static_pad("src").ok_or(anyhow!("{} has no src pad", element.name()))?;
+        let probe_last_packet = last_packet.clone();
+        src_pad.add_probe(PadProbeType::BUFFER | PadProbeType::BUFFER_LIST, move |_, _| {
+            *probe_last_packet.lock().unwrap() = Instant::now();
+            PadProbeReturn::Ok
+        });
+        Ok(Self { last_packet })
+    }
+
+    /// Time since the last packet
+    pub fn silence(&self) -> Duration {
+        self.last_packet.lock().unwrap().elapsed()
+    }
+}
+
+/// The video stream described by a SDP file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdpSession {
+    /// Port of the video media
+    pub port: i32,
+    /// Connection address of the video media (or of the session)
+    pub address: Option<String>,
+    /// Senders of a source-filter (RFC 4570) including them
+    pub sources: Vec<String>,
+    pub settings: RtpSettings,
+}
+
+/// Read a SDP file
+pub fn parse_sdp_file(path: impl AsRef<Path>) -> Result<SdpSession, anyhow::Error> {
+    let bytes = std::fs::read(path)?;
+    parse_sdp(&String::from_utf8_lossy(&bytes))
+}
+
+/// Parse the first video media of a SDP: its port, its connection (or the one of the session), the source-filters
+/// and the a=rtpmap, a=fmtp and a=framerate of its first format with a supported codec
+pub fn parse_sdp(content: &str) -> Result<SdpSession, anyhow::Error> {
+    let message = SDPMessage::parse_buffer(content.as_bytes()).map_err(|err| anyhow!("Invalid SDP : {}", err))?;
+    let Some(media) = message.medias().find(|media| media.media() == Some("video")) else {
+        return Err(anyhow!("No video media in the SDP"));
+    };
+    // c=IN IP4 239.1.1.1/32, the one of the session applies to the medias without their own
+    let address = media
+        .connections()
+        .next()
+        .or(message.connection())
+        .and_then(|connection| connection.address())
+        .map(|address| address.split('/').next().unwrap_or(address).to_string());
+    // a=source-filter: incl IN IP4 239.1.1.1 192.168.1.10 192.168.1.11
+    let sources = message
+        .attributes()
+        .chain(media.attributes())
+        .filter(|attribute| attribute.key() == "source-filter")
+        .filter_map(|attribute| attribute.value())
+        .flat_map(|filter| match filter.split_whitespace().collect::<Vec<&str>>()[..] {
+            ["incl", _, _, _, ref filter_sources @ ..] => filter_sources.iter().map(|s| s.to_string()).collect(),
+            _ => vec![],
+        })
+        .collect();
+
+    // m=video 5000 RTP/AVP 96 97 with a=rtpmap:96 H264/90000
+    let payloads: Vec<i32> = media.formats().filter_map(|format| format.parse().ok()).collect();
+    let rtpmaps: Vec<(i32, &str)> = payloads
+        .iter()
+        .filter_map(|&payload| payload_attributes(media, "rtpmap", payload).next().map(|map| (payload, map)))
+        .collect();
+    let encoding_name = |map: &str| map.split('/').next().unwrap_or("").to_string();
+    let Some((payload, codec, rtpmap)) = rtpmaps.iter().find_map(|&(payload, map)| {
+        RtpCodec::from_encoding_name(&encoding_name(map)).map(|codec| (payload, codec, map))
+    }) else {
+        return Err(match rtpmaps.first() {
+            Some((_, map)) => anyhow!("Unsupported codec {} in the SDP", encoding_name(map)),
+            None => anyhow!("No rtpmap for the payload {} in the SDP", payloads.first().copied().unwrap_or_default()),
+        });
+    };
+    let clock_rate = rtpmap.split('/').nth(1).and_then(|rate| rate.parse().ok()).unwrap_or(90000);
+
+    // a=fmtp:96 packetization-mode=1; sprop-parameter-sets=...
+    let mut parameters: Vec<(String, String)> = payload_attributes(media, "fmtp", payload)
+        .flat_map(|fmtp| fmtp.split(';'))
+        .filter_map(|parameter| parameter.split_once('='))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    if let Some(framerate) = media.attribute_val("framerate") {
+        parameters.push(("a-framerate".into(), framerate.trim().to_string()));
+    }
+
+    Ok(SdpSession {
+        port: media.port() as i32,
+        address,
+        sources,
+        settings: RtpSettings { codec, payload, clock_rate, parameters, ..Default::default() },
+    })
+}
+
+/// The values of the `key` attributes of a media for this payload type (a=rtpmap:96 H264/90000 -> H264/90000)
+fn payload_attributes<'a>(media: &'a SDPMediaRef, key: &'a str, payload: i32) -> impl Iterator<Item = &'a str> {
+    media
+        .attributes()
+        .filter(move |attribute| attribute.key() == key)
+        .filter_map(|attribute| attribute.value())
+        .filter_map(move |value| {
+            let (pt, rest) = value.trim().split_once(' ')?;
+            (pt.parse::<i32>().ok() == Some(payload)).then_some(rest.trim())
+        })
+}
@@ -0,0 +1,100 @@
+use gstreamer::{
+    glib::ValueArray,
+    prelude::{Cast, GstBinExt, ObjectExt},
+    Bin, Element, ElementFactory, Structure, StructureRef,
+};
+
+/// Name of the srtsrc of a Srt video (see srt_stats())
+pub const SRT_SOURCE: &str = "srtsrc";
+
+/// Who opens the SRT connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SrtMode {
+    /// Connect to the sender listening at srt://host:port
+    #[default]
+    Caller,
+    /// Wait for the sender on srt://:port (or srt://local_address:port)
+    Listener,
+}
+
+impl SrtMode {
+    /// The value of the `mode` property of srtsrc
+    pub fn name(&self) -> &'static str {
+        match self {
+            SrtMode::Caller => "caller",
+            SrtMode::Listener => "listener",
+        }
+    }
+}
+
+/// Create a srtsrc for this uri. `latency` is the SRT latency in ms (the larger of the sender and receiver ones is
+/// used), the passphrase (10 to 79 characters) must be the one of the sender.
+pub fn srt_source(uri: &str, latency: u32, passphrase: Option<&str>, mode: SrtMode) -> Result<Element, anyhow::Error> {
+    let srtsrc = ElementFactory::make("srtsrc")
+        .name(SRT_SOURCE)
+        .property("uri", uri)
+        .property("latency", latency as i32)
+        .build()?;
+    srtsrc.set_property_from_str("mode", mode.name());
+    if let Some(passphrase) = passphrase {
+        srtsrc.set_property("passphrase", passphrase);
+    }
+    Ok(srtsrc)
+}
+
+/// The statistics of a SRT connection
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SrtStats {
+    /// Round trip time in ms
+    pub rtt: f64,
+    pub packets_received: i64,
+    /// Packets never received
+    pub packets_lost: i64,
+    /// Packets received too late and dropped
+    pub packets_dropped: i64,
+    pub packets_retransmitted: i64,
+    /// Estimated bandwidth of the link in Mb/s
+    pub bandwidth: f64,
+    /// Receive rate in Mb/s
+    pub receive_rate: f64,
+}
+
+impl SrtStats {
+    /// Read the `stats` structure of a srtsrc. A listener has the statistics of each caller, the first one is used.
+    pub fn from_structure(stats: &StructureRef) -> Option<Self> {
+        if let Ok(callers) = stats.get::<ValueArray>("callers") {
+            let caller = callers.iter().find_map(|caller| caller.get::<Structure>().ok())?;
+            return Self::from_structure(&caller);
+        }
+        stats.has_field("packets-received").then(|| Self {
+            rtt: number(stats, "rtt-ms"),
+            packets_received: number(stats, "packets-received") as i64,
+            packets_lost: number(stats, "packets-received-lost") as i64,
+            packets_dropped: number(stats, "packets-received-dropped") as i64,
+            packets_retransmitted: number(stats, "packets-received-retransmitted") as i64,
+            bandwidth: number(stats, "bandwidth-mbps"),
+            receive_rate: number(stats, "receive-rate-mbps"),
+        })
+    }
+}
+
+/// A numeric field of the SRT statistics, whatever its type (they changed between the GStreamer versions)
+fn number(stats: &StructureRef, field: &str) -> f64 {
+    let Ok(value) = stats.value(field) else {
+        return 0.0;
+    };
+    value
+        .get::<f64>()
+        .ok()
+        .or_else(|| value.get::<i64>().ok().map(|value| value as f64))
+        .or_else(|| value.get::<u64>().ok().map(|value| value as f64))
+        .or_else(|| value.get::<i32>().ok().map(f64::from))
+        .or_else(|| value.get::<u32>().ok().map(f64::from))
+        .unwrap_or(0.0)
+}
+
+/// The statistics of the srtsrc of this pipeline, None until a connection is established
+pub fn srt_stats(pipeline: &Element) -> Option<SrtStats> {
+    let srtsrc = pipeline.downcast_ref::<Bin>()?.by_name(SRT_SOURCE)?;
+    SrtStats::from_structure(&srtsrc.property::<Structure>("stats"))
+}
@@ -0,0 +1,143 @@
+use std::sync::{Arc, Mutex};
+
+use gstreamer::{prelude::ElementExtManual, ClockTime, Element};
+
+use crate::{
+    adaptive::AdaptiveStatus,
+    rtp::RtpStats,
+    srt::SrtStats,
+    tracks::{TrackInfo, TrackKind},
+};
+
+/// Where the stream of a video stepper is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamState {
+    /// Not launched yet (or no pipeline)
+    #[default]
+    Idle,
+    /// Waiting for data, see PlaybackStatus::buffering for the percentage
+    Buffering,
+    /// The first frame is ready, the pipeline is paused for the first time
+    Prerolled,
+    Playing,
+    Paused,
+    /// End of stream reached
+    Eos,
+    /// The pipeline has been closed after an error, see PlaybackStatus::error
+    Error,
+    /// The live source has been lost, a new pipeline will be launched
+    Reconnecting,
+    /// No packet received for a while, the pipeline keeps playing and waits for them
+    SignalLost,
+}
+
+impl StreamState {
+    /// The text shown on the screen in this state
+    pub fn screen_text(&self) -> Option<&'static str> {
+        match self {
+            StreamState::Reconnecting => Some("Reconnecting..."),
+            StreamState::SignalLost => Some("Signal lost"),
+            _ => None,
+        }
+    }
+}
+
+/// The playback status of a Video1/Video2, updated by the stepper from the bus and from position/duration queries
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaybackStatus {
+    pub state: StreamState,
+    pub position: Option<ClockTime>,
+    pub duration: Option<ClockTime>,
+    /// Buffering percentage (100 when not buffering)
+    pub buffering: i32,
+    pub rate: f64,
+    pub error: Option<String>,
+    /// Size of the decoded frames
+    pub video_size: Option<(u32, u32)>,
+    /// Size of the texture of the screen, set by the stepper on the main thread. The frames of another size are dropped
+    /// until the texture is resized to video_size.
+    pub texture_size: Option<(u32, u32)>,
+    /// Display aspect ratio of the video (pixel-aspect-ratio included)
+    pub display_aspect: Option<f32>,
+    /// The tracks of the media (decodebin3/playbin3 only)
+    pub tracks: Vec<TrackInfo>,
+    /// Packet statistics of a RtpStream
+    pub rtp_stats: Option<RtpStats>,
+    /// Statistics of the connection of a Srt video
+    pub srt_stats: Option<SrtStats>,
+    /// Variants of a HLS or DASH stream
+    pub adaptive: Option<AdaptiveStatus>,
+    /// Video frames uploaded to the texture
+    pub frames: u64,
+    /// Audio samples (per channel) written to the sounds
+    pub audio_samples: u64,
+    /// The decoders chosen for the streams (see DecoderResolver)
+    pub decoders: Vec<String>,
+}
+
+impl Default for PlaybackStatus {
+    fn default() -> Self {
+        Self {
+            state: StreamState::Idle,
+            position: None,
+            duration: None,
+            buffering: 100,
+            rate: 1.0,
+            error: None,
+            video_size: None,
+            texture_size: None,
+            display_aspect: None,
+            tracks: vec![],
+            rtp_stats: None,
+            srt_stats: None,
+            adaptive: None,
+            frames: 0,
+            audio_samples: 0,
+            decoders: vec![],
+        }
+    }
+}
+
+/// The status shared between a stepper and the application
+pub type PlaybackStatusHandle = Arc<Mutex<PlaybackStatus>>;
+
+impl PlaybackStatus {
+    /// Position / duration in [0.0, 1.0] if both are known
+    pub fn progress(&self) -> Option<f32> {
+        match (self.position, self.duration) {
+            (Some(position), Some(duration)) if duration > ClockTime::ZERO => {
+                Some((position.nseconds() as f64 / duration.nseconds() as f64).clamp(0.0, 1.0) as f32)
+            }
+            _ => None,
+        }
+    }
+
+    /// Is the stream running (no EOS, no error)
+    pub fn is_running(&self) -> bool {
+        !matches!(self.state, StreamState::Idle | StreamState::Eos | StreamState::Error)
+    }
+
+    /// Query position and duration of the pipeline. A live stream has no duration.
+    pub fn query(&mut self, pipeline: &Element) {
+        if let Some(position) = pipeline.query_position::<ClockTime>() {
+            self.position = Some(position);
+        }
+        if self.duration.is_none() {
+            self.duration = pipeline.query_duration::<ClockTime>();
+        }
+    }
+
+    /// The selected track of this kind if any
+    pub fn selected_track(&self, kind: TrackKind) -> Option<&TrackInfo> {
+        self.tracks.iter().find(|track| track.kind == kind && track.selected)
+    }
+
+    /// A readable position/duration (ex: "01:02 / 10:00")
+    pub fn time_text(&self) -> String {
+        let fmt = |time: Option<ClockTime>| match time {
+            Some(time) => format!("{:02}:{:02}", time.seconds() / 60, time.seconds() % 60),
+            None => "--:--".into(),
+        };
+        format!("{} / {}", fmt(self.position), fmt(self.duration))
+    }
+}
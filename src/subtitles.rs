@@ -0,0 +1,204 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Ok;
+use gstreamer::{element_error, ClockTime};
+use gstreamer_app::{AppSink, AppSinkCallbacks};
+use stereokit_rust::maths::{Matrix, Quat, Vec2, Vec3};
+
+/// Used when an embedded text buffer has no duration
+const DEFAULT_CUE_DURATION: ClockTime = ClockTime::from_seconds(4);
+
+/// A subtitle to show from `start` to `end` (stream time of the media)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtitleCue {
+    pub start: ClockTime,
+    pub end: ClockTime,
+    /// Plain text (markup removed), lines separated by '\n'
+    pub text: String,
+}
+
+/// The subtitles of a screen: the cues received from the selected embedded text track and the cues of a sidecar
+/// file. The sidecar file, when loaded, replaces the embedded track.
+#[derive(Debug)]
+pub struct Subtitles {
+    pub visible: bool,
+    embedded: Vec<SubtitleCue>,
+    sidecar: Option<Vec<SubtitleCue>>,
+}
+
+impl Default for Subtitles {
+    fn default() -> Self {
+        Self { visible: true, embedded: vec![], sidecar: None }
+    }
+}
+
+/// The subtitles shared between a stepper and its text appsink
+pub type SubtitlesHandle = Arc<Mutex<Subtitles>>;
+
+impl Subtitles {
+    /// Add a cue of the embedded track. The cues sent again after a seek are ignored.
+    pub fn add_cue(&mut self, cue: SubtitleCue) {
+        let index = self.embedded.partition_point(|c| c.start < cue.start);
+        if self.embedded[index..].iter().take_while(|c| c.start == cue.start).any(|c| c.text == cue.text) {
+            return;
+        }
+        self.embedded.insert(index, cue);
+    }
+
+    /// Forget the cues of the embedded track ended before this position when playing forward, a seek backward sends
+    /// them again
+    pub fn prune_embedded(&mut self, position: ClockTime) {
+        self.embedded.retain(|cue| cue.end > position);
+    }
+
+    /// Forget the cues of the embedded track (a new track or a new media)
+    pub fn clear_embedded(&mut self) {
+        self.embedded.clear();
+    }
+
+    /// Show the cues of a sidecar file instead of the embedded track (None to get back the embedded track)
+    pub fn set_sidecar(&mut self, cues: Option<Vec<SubtitleCue>>) {
+        self.sidecar = cues.map(|mut cues| {
+            cues.sort_by_key(|cue| cue.start);
+            cues
+        });
+    }
+
+    pub fn has_sidecar(&self) -> bool {
+        self.sidecar.is_some()
+    }
+
+    /// The text to show at this position of the media if any
+    pub fn text_at(&self, position: ClockTime) -> Option<String> {
+        if !self.visible {
+            return None;
+        }
+        let cues = self.sidecar.as_ref().unwrap_or(&self.embedded);
+        let started = cues.partition_point(|cue| cue.start <= position);
+        let lines: Vec<&str> =
+            cues[..started].iter().filter(|cue| cue.end > position).map(|cue| cue.text.as_str()).collect();
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+}
+
+/// Read a SRT or WebVTT file
+pub fn parse_subtitle_file(path: impl AsRef<Path>) -> Result<Vec<SubtitleCue>, anyhow::Error> {
+    let bytes = std::fs::read(path)?;
+    Ok(parse_subtitles(&String::from_utf8_lossy(&bytes)))
+}
+
+/// Parse the content of a SRT or WebVTT file. Blocks without timing (WEBVTT header, NOTE, STYLE) are skipped.
+pub fn parse_subtitles(content: &str) -> Vec<SubtitleCue> {
+    let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut cues = vec![];
+    for block in content.split("\n\n") {
+        let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+        let Some(timing) = lines.next() else {
+            continue;
+        };
+        // 00:00:01,000 --> 00:00:04,000 (SRT) or 00:01.000 --> 00:04.000 align:start (WebVTT)
+        let Some((start, end)) = timing.split_once("-->") else {
+            continue;
+        };
+        let end = end.split_whitespace().next().unwrap_or("");
+        let (Some(start), Some(end)) = (parse_timestamp(start.trim()), parse_timestamp(end)) else {
+            continue;
+        };
+        let text: Vec<String> = lines.map(strip_markup).collect();
+        cues.push(SubtitleCue { start, end, text: text.join("\n") });
+    }
+    cues
+}
+
+/// hh:mm:ss,ttt or hh:mm:ss.ttt or mm:ss.ttt
+fn parse_timestamp(value: &str) -> Option<ClockTime> {
+    let value = value.replace(',', ".");
+    let (clock, millis) = value.split_once('.').unwrap_or((&value, "0"));
+    let mut seconds = 0u64;
+    for part in clock.split(':') {
+        seconds = seconds * 60 + part.parse::<u64>().ok()?;
+    }
+    let millis = format!("{:0<3}", millis).get(..3)?.parse::<u64>().ok()?;
+    Some(ClockTime::from_mseconds(seconds * 1000 + millis))
+}
+
+/// Remove the tags (<i>, <font ...>, <c.yellow>, <v Bob>, ...) and the usual entities
+pub fn strip_markup(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    plain.replace("&lt;", "<").replace("&gt;", ">").replace("&nbsp;", " ").replace("&amp;", "&")
+}
+
+/// A .srt or .vtt file with the same name as the media, if any
+pub fn find_sidecar(media_path: &str) -> Option<PathBuf> {
+    ["srt", "vtt"]
+        .iter()
+        .map(|ext| Path::new(media_path).with_extension(ext))
+        .find(|path| path.is_file())
+}
+
+/// The appsink receiving the raw text of the selected subtitle track
+pub fn text_appsink(subtitles: SubtitlesHandle) -> AppSink {
+    let caps = gstreamer::Caps::builder("text/x-raw").field("format", gstreamer::List::new(["utf8", "pango-markup"]));
+    // a subtitle track is sparse, it must not block the preroll nor wait for its time
+    let appsink = AppSink::builder().caps(&caps.build()).sync(false).async_(false).build();
+    set_text_callback(appsink.clone(), subtitles);
+    appsink
+}
+
+/// Each text buffer becomes a cue, its timestamps are converted to the stream time of the media
+fn set_text_callback(appsink: AppSink, subtitles: SubtitlesHandle) {
+    appsink.set_callbacks(
+        AppSinkCallbacks::builder()
+            .new_sample(move |appsink| {
+                let sample = appsink.pull_sample().map_err(|_| gstreamer::FlowError::Eos)?;
+                let buffer = sample.buffer().ok_or_else(|| {
+                    element_error!(appsink, gstreamer::ResourceError::Failed, ("Failed to get buffer from appsink"));
+
+                    gstreamer::FlowError::Error
+                })?;
+                let Some(pts) = buffer.pts() else {
+                    return Result::<gstreamer::FlowSuccess, gstreamer::FlowError>::Ok(gstreamer::FlowSuccess::Ok);
+                };
+                let segment = sample.segment().and_then(|segment| segment.downcast_ref::<ClockTime>().cloned());
+                let start = segment.as_ref().and_then(|segment| segment.to_stream_time(pts)).unwrap_or(pts);
+                let end = start + buffer.duration().unwrap_or(DEFAULT_CUE_DURATION);
+
+                let map = buffer.map_readable().map_err(|_| {
+                    element_error!(appsink, gstreamer::ResourceError::Failed, ("Failed to map Text buffer readable"));
+
+                    gstreamer::FlowError::Error
+                })?;
+                let text = strip_markup(String::from_utf8_lossy(map.as_slice()).trim_end_matches('\0'));
+                if !text.trim().is_empty() {
+                    subtitles.lock().unwrap().add_cue(SubtitleCue { start, end, text });
+                }
+                Result::<gstreamer::FlowSuccess, gstreamer::FlowError>::Ok(gstreamer::FlowSuccess::Ok)
+            })
+            .build(),
+    );
+}
+
+/// Where to draw the subtitles of a screen of `size` (width, height) drawn with `transform_screen`: under its bottom
+/// edge, facing the viewer
+pub fn subtitle_transform(transform_screen: Matrix, size: Vec2) -> Matrix {
+    // the screen is a plane generated up (x right, z down) so y is toward the viewer
+    let position = transform_screen.transform_point(Vec3::new(0.0, 0.01, size.y / 2.0 + 0.02));
+    let toward_viewer = transform_screen.transform_normal(Vec3::Y);
+    Matrix::tr(&position, &Quat::look_dir(toward_viewer))
+}
@@ -0,0 +1,127 @@
+use anyhow::{anyhow, Ok};
+use gstreamer::{event::SelectStreams, prelude::ElementExt, tags, Element, Stream, StreamCollection, StreamType};
+
+/// The kind of a track of a media
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackKind {
+    Audio,
+    Video,
+    Text,
+    /// Container or unknown stream
+    Other,
+}
+
+impl TrackKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TrackKind::Audio => "audio",
+            TrackKind::Video => "video",
+            TrackKind::Text => "text",
+            TrackKind::Other => "other",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "audio" => Some(TrackKind::Audio),
+            "video" => Some(TrackKind::Video),
+            "text" => Some(TrackKind::Text),
+            "other" => Some(TrackKind::Other),
+            _ => None,
+        }
+    }
+
+    fn from_stream_type(stream_type: StreamType) -> Self {
+        if stream_type.contains(StreamType::AUDIO) {
+            TrackKind::Audio
+        } else if stream_type.contains(StreamType::VIDEO) {
+            TrackKind::Video
+        } else if stream_type.contains(StreamType::TEXT) {
+            TrackKind::Text
+        } else {
+            TrackKind::Other
+        }
+    }
+}
+
+/// A track of the stream collection of decodebin3/playbin3
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackInfo {
+    pub stream_id: String,
+    pub kind: TrackKind,
+    /// Index of the track among the tracks of the same kind
+    pub index: usize,
+    /// ISO-639 code if the media tells it
+    pub language: Option<String>,
+    pub title: Option<String>,
+    /// Codec description from the tags, or the media type of the caps
+    pub codec: Option<String>,
+    /// Bits per second (bitrate or nominal bitrate tag)
+    pub bitrate: Option<u32>,
+    pub selected: bool,
+}
+
+/// The tracks of a stream collection. Nothing is selected until the StreamsSelected message.
+pub fn tracks_from_collection(collection: &StreamCollection) -> Vec<TrackInfo> {
+    let mut tracks: Vec<TrackInfo> = vec![];
+    for stream in collection.iter() {
+        let kind = TrackKind::from_stream_type(stream.stream_type());
+        let index = tracks.iter().filter(|track| track.kind == kind).count();
+        tracks.push(track_from_stream(&stream, kind, index));
+    }
+    tracks
+}
+
+fn track_from_stream(stream: &Stream, kind: TrackKind, index: usize) -> TrackInfo {
+    let stream_id = stream.stream_id().map(|id| id.to_string()).unwrap_or_default();
+    let mut track =
+        TrackInfo { stream_id, kind, index, language: None, title: None, codec: None, bitrate: None, selected: false };
+    if let Some(tag_list) = stream.tags() {
+        track.language = tag_list.get::<tags::LanguageCode>().map(|v| v.get().to_string());
+        track.title = tag_list.get::<tags::Title>().map(|v| v.get().to_string());
+        track.codec = match kind {
+            TrackKind::Audio => tag_list.get::<tags::AudioCodec>().map(|v| v.get().to_string()),
+            TrackKind::Video => tag_list.get::<tags::VideoCodec>().map(|v| v.get().to_string()),
+            TrackKind::Text => tag_list.get::<tags::SubtitleCodec>().map(|v| v.get().to_string()),
+            TrackKind::Other => None,
+        }
+        .or_else(|| tag_list.get::<tags::Codec>().map(|v| v.get().to_string()));
+        track.bitrate =
+            tag_list.get::<tags::Bitrate>().or_else(|| tag_list.get::<tags::NominalBitrate>()).map(|v| v.get());
+    }
+    if track.codec.is_none() {
+        track.codec = stream.caps().and_then(|caps| caps.structure(0).map(|s| s.name().to_string()));
+    }
+    track
+}
+
+/// Mark the tracks given by the StreamsSelected message
+pub fn set_selected(tracks: &mut [TrackInfo], stream_ids: &[String]) {
+    for track in tracks {
+        track.selected = stream_ids.contains(&track.stream_id);
+    }
+}
+
+/// The stream ids to select for having the track `index` of `kind` in place of the current track of this kind
+pub fn selection_with(tracks: &[TrackInfo], kind: TrackKind, index: usize) -> Result<Vec<String>, anyhow::Error> {
+    let Some(new_track) = tracks.iter().find(|track| track.kind == kind && track.index == index) else {
+        return Err(anyhow!("No {} track {}", kind.name(), index));
+    };
+    let mut stream_ids: Vec<String> = tracks
+        .iter()
+        .filter(|track| track.selected && track.kind != kind)
+        .map(|track| track.stream_id.clone())
+        .collect();
+    stream_ids.push(new_track.stream_id.clone());
+    Ok(stream_ids)
+}
+
+/// Send a select-streams event to decodebin3/playbin3
+pub fn select_streams(element: &Element, stream_ids: &[String]) -> Result<(), anyhow::Error> {
+    let event = SelectStreams::new(stream_ids.iter().map(|id| id.as_str()));
+    if element.send_event(event) {
+        Ok(())
+    } else {
+        Err(anyhow!("Stream selection {:?} refused", stream_ids))
+    }
+}
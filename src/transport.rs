@@ -0,0 +1,212 @@
+use anyhow::{anyhow, Ok};
+use gstreamer::{
+    event::{Seek, Step},
+    prelude::{Cast, ElementExt, ElementExtManual, GstBinExt, GstObjectExt, ObjectExt, PadExt},
+    Bin, ClockTime, Element, Event, SeekFlags, SeekType, State,
+};
+use stereokit_rust::event_loop::{StepperAction, StepperId};
+
+use crate::tracks::TrackKind;
+
+/// Event keys understood by Video1 and Video2. The StepperId of the StepperAction::Event is the targeted screen.
+pub const PLAYER_PLAY: &str = "PlayerPlay";
+pub const PLAYER_PAUSE: &str = "PlayerPause";
+pub const PLAYER_SEEK: &str = "PlayerSeek";
+pub const PLAYER_SEEK_BY: &str = "PlayerSeekBy";
+pub const PLAYER_FRAME_STEP: &str = "PlayerFrameStep";
+pub const PLAYER_RATE: &str = "PlayerRate";
+pub const PLAYER_VOLUME: &str = "PlayerVolume";
+pub const PLAYER_MUTE: &str = "PlayerMute";
+/// The value is "band:gain"
+pub const PLAYER_EQUALIZER: &str = "PlayerEqualizer";
+/// The value is "kind:index" (ex: "audio:1")
+pub const PLAYER_SELECT_TRACK: &str = "PlayerSelectTrack";
+pub const PLAYER_SUBTITLES: &str = "PlayerSubtitles";
+/// The value is the index of the variant or "auto"
+pub const PLAYER_VARIANT: &str = "PlayerVariant";
+
+const SEEK_FLAGS: SeekFlags = SeekFlags::FLUSH.union(SeekFlags::ACCURATE);
+
+/// The commands a video stepper can receive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerCommand {
+    Play,
+    Pause,
+    /// Seek to this position in seconds
+    Seek(f64),
+    /// Seek forward (or backward if negative) of this number of seconds
+    SeekBy(f64),
+    /// Pause and show the next frame (previous one if the rate is negative)
+    FrameStep,
+    /// Playback rate, negative values play backward
+    Rate(f64),
+    /// Volume from 0.0 to 10.0 (1.0 is the original level)
+    Volume(f64),
+    Mute(bool),
+    /// Gain in dB (-24.0 to 12.0) of a band of the equalizer
+    Equalizer(usize, f64),
+    /// Select the track `index` of this kind (see TrackInfo::index)
+    SelectTrack(TrackKind, usize),
+    /// Show or hide the subtitles
+    Subtitles(bool),
+    /// Play this variant of a HLS or DASH stream (see AdaptiveStatus::variants), None for the automatic selection
+    Variant(Option<usize>),
+}
+
+impl PlayerCommand {
+    /// The event to push with sk.push_action() or an event_loop_proxy to drive the screen `screen_id`
+    pub fn to_event(&self, screen_id: impl Into<StepperId>) -> StepperAction {
+        let (key, value) = match self {
+            PlayerCommand::Play => (PLAYER_PLAY, "".to_string()),
+            PlayerCommand::Pause => (PLAYER_PAUSE, "".to_string()),
+            PlayerCommand::Seek(position) => (PLAYER_SEEK, position.to_string()),
+            PlayerCommand::SeekBy(delta) => (PLAYER_SEEK_BY, delta.to_string()),
+            PlayerCommand::FrameStep => (PLAYER_FRAME_STEP, "".to_string()),
+            PlayerCommand::Rate(rate) => (PLAYER_RATE, rate.to_string()),
+            PlayerCommand::Volume(volume) => (PLAYER_VOLUME, volume.to_string()),
+            PlayerCommand::Mute(mute) => (PLAYER_MUTE, mute.to_string()),
+            PlayerCommand::Equalizer(band, gain) => (PLAYER_EQUALIZER, format!("{}:{}", band, gain)),
+            PlayerCommand::SelectTrack(kind, index) => (PLAYER_SELECT_TRACK, format!("{}:{}", kind.name(), index)),
+            PlayerCommand::Subtitles(visible) => (PLAYER_SUBTITLES, visible.to_string()),
+            PlayerCommand::Variant(index) => {
+                (PLAYER_VARIANT, index.map(|index| index.to_string()).unwrap_or_else(|| "auto".into()))
+            }
+        };
+        StepperAction::event(screen_id.into(), key, &value)
+    }
+
+    /// Read back a command from the key/value of a StepperAction::Event
+    pub fn from_event(key: &str, value: &str) -> Option<Self> {
+        match key {
+            PLAYER_PLAY => Some(PlayerCommand::Play),
+            PLAYER_PAUSE => Some(PlayerCommand::Pause),
+            PLAYER_SEEK => value.parse().ok().map(PlayerCommand::Seek),
+            PLAYER_SEEK_BY => value.parse().ok().map(PlayerCommand::SeekBy),
+            PLAYER_FRAME_STEP => Some(PlayerCommand::FrameStep),
+            PLAYER_RATE => value.parse().ok().map(PlayerCommand::Rate),
+            PLAYER_VOLUME => value.parse().ok().map(PlayerCommand::Volume),
+            PLAYER_MUTE => value.parse().ok().map(PlayerCommand::Mute),
+            PLAYER_EQUALIZER => {
+                let (band, gain) = value.split_once(':')?;
+                Some(PlayerCommand::Equalizer(band.parse().ok()?, gain.parse().ok()?))
+            }
+            PLAYER_SELECT_TRACK => {
+                let (kind, index) = value.split_once(':')?;
+                Some(PlayerCommand::SelectTrack(TrackKind::from_name(kind)?, index.parse().ok()?))
+            }
+            PLAYER_SUBTITLES => value.parse().ok().map(PlayerCommand::Subtitles),
+            PLAYER_VARIANT => match value {
+                "auto" => Some(PlayerCommand::Variant(None)),
+                index => index.parse().ok().map(|index| PlayerCommand::Variant(Some(index))),
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Apply a command to a pipeline. `rate` is the current playback rate of the stepper and is updated by
+/// PlayerCommand::Rate
+pub fn apply(pipeline: &Element, rate: &mut f64, command: PlayerCommand) -> Result<(), anyhow::Error> {
+    match command {
+        PlayerCommand::Play => {
+            pipeline.set_state(State::Playing)?;
+        }
+        PlayerCommand::Pause => {
+            pipeline.set_state(State::Paused)?;
+        }
+        PlayerCommand::Seek(position) => seek(pipeline, *rate, seconds(position))?,
+        PlayerCommand::SeekBy(delta) => {
+            let position = pipeline.query_position::<ClockTime>().ok_or(anyhow!("Unable to get the position"))?;
+            let target = position.seconds_f64() + delta;
+            seek(pipeline, *rate, seconds(target))?
+        }
+        PlayerCommand::FrameStep => frame_step(pipeline, *rate)?,
+        PlayerCommand::Rate(new_rate) => {
+            if new_rate == 0.0 {
+                return Err(anyhow!("A rate of 0 is not allowed, use Pause"));
+            }
+            let position = pipeline.query_position::<ClockTime>().unwrap_or(ClockTime::ZERO);
+            if *rate < 0.0 && new_rate > 0.0 {
+                // the stop of the backward segment is removed, the next forward seeks keep the end of the media
+                let seek_event =
+                    Seek::new(new_rate, SEEK_FLAGS, SeekType::Set, position, SeekType::Set, ClockTime::NONE);
+                send_seek(pipeline, seek_event, new_rate, position)?;
+            } else {
+                seek(pipeline, new_rate, position)?;
+            }
+            *rate = new_rate;
+        }
+        PlayerCommand::Volume(_)
+        | PlayerCommand::Mute(_)
+        | PlayerCommand::Equalizer(..)
+        | PlayerCommand::SelectTrack(..)
+        | PlayerCommand::Subtitles(_)
+        | PlayerCommand::Variant(_) => {
+            return Err(anyhow!("{:?} is not a transport command", command));
+        }
+    }
+    Ok(())
+}
+
+/// Flushing seek keeping the given rate. When playing forward the stop is not changed, when playing backward the
+/// position is the end of the played segment.
+pub fn seek(pipeline: &Element, rate: f64, position: ClockTime) -> Result<(), anyhow::Error> {
+    let seek_event = if rate > 0.0 {
+        Seek::new(rate, SEEK_FLAGS, SeekType::Set, position, SeekType::None, ClockTime::NONE)
+    } else {
+        Seek::new(rate, SEEK_FLAGS, SeekType::Set, ClockTime::ZERO, SeekType::Set, position)
+    };
+    send_seek(pipeline, seek_event, rate, position)
+}
+
+fn send_seek(pipeline: &Element, seek_event: Event, rate: f64, position: ClockTime) -> Result<(), anyhow::Error> {
+    if pipeline.send_event(seek_event) {
+        Ok(())
+    } else {
+        Err(anyhow!("Seek to {} at rate {} refused", position, rate))
+    }
+}
+
+/// Segment seek to the beginning of the media. A SegmentDone message is posted instead of EOS at the end, then a
+/// new non flushing segment seek gives a seamless loop.
+pub fn segment_seek(pipeline: &Element, rate: f64, flush: bool) -> Result<(), anyhow::Error> {
+    let flags = if flush { SeekFlags::SEGMENT | SeekFlags::FLUSH } else { SeekFlags::SEGMENT };
+    let seek_event = Seek::new(rate, flags, SeekType::Set, ClockTime::ZERO, SeekType::End, ClockTime::ZERO);
+    if pipeline.send_event(seek_event) {
+        Ok(())
+    } else {
+        Err(anyhow!("Segment seek refused"))
+    }
+}
+
+/// Pause the pipeline then step one video frame in the direction of the rate
+pub fn frame_step(pipeline: &Element, rate: f64) -> Result<(), anyhow::Error> {
+    if pipeline.current_state() != State::Paused {
+        pipeline.set_state(State::Paused)?;
+    }
+    let step_event = Step::new(gstreamer::format::Buffers::ONE, rate.abs(), true, false);
+    let video_sink = video_sink(pipeline).ok_or(anyhow!("No video sink to step"))?;
+    if video_sink.send_event(step_event) {
+        Ok(())
+    } else {
+        Err(anyhow!("Frame step refused by {}", video_sink.name()))
+    }
+}
+
+/// The video sink of a playbin or the first sink receiving video/x-raw in a pipeline
+fn video_sink(pipeline: &Element) -> Option<Element> {
+    if pipeline.has_property("video-sink", None) {
+        return pipeline.property::<Option<Element>>("video-sink");
+    }
+    let bin = pipeline.downcast_ref::<Bin>()?;
+    bin.iterate_sinks().into_iter().flatten().find(|sink| {
+        sink.static_pad("sink")
+            .and_then(|pad| pad.current_caps())
+            .and_then(|caps| caps.structure(0).map(|s| s.name().starts_with("video/")))
+            .unwrap_or(false)
+    })
+}
+
+fn seconds(value: f64) -> ClockTime {
+    ClockTime::from_nseconds((value.max(0.0) * 1_000_000_000.0) as u64)
+}
@@ -0,0 +1,199 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use anyhow::{anyhow, Ok};
+use gstreamer::{
+    element_error,
+    prelude::{ElementExt, ObjectExt},
+    Caps, Element, ElementFactory, Promise, ResourceError, Structure,
+};
+use gstreamer_sdp::SDPMessage;
+use gstreamer_webrtc::{
+    WebRTCICEGatheringState, WebRTCPeerConnectionState, WebRTCRTPTransceiver, WebRTCRTPTransceiverDirection,
+    WebRTCSDPType, WebRTCSessionDescription,
+};
+
+use crate::adaptive::resolve_uri;
+
+/// Name of the webrtcbin of a Whep video
+pub const WHEP_WEBRTCBIN: &str = "webrtcbin";
+
+/// The session of a WHEP endpoint, shared with the webrtcbin callbacks. The resource created by the endpoint is
+/// deleted by close(), or when the last clone of the session is dropped.
+#[derive(Debug, Clone)]
+pub struct WhepSession(Arc<WhepResource>);
+
+impl WhepSession {
+    /// The url of the resource (Location of the answer of the endpoint) once negotiated
+    pub fn resource(&self) -> Option<String> {
+        self.0.url.lock().unwrap().clone()
+    }
+
+    /// Delete the resource now. The DELETE is sent once: the next calls and the drop do nothing.
+    pub fn close(&self) {
+        self.0.delete();
+    }
+}
+
+#[derive(Debug)]
+struct WhepResource {
+    url: Mutex<Option<String>>,
+    token: Option<String>,
+}
+
+impl WhepResource {
+    fn delete(&self) {
+        let Some(resource) = self.url.lock().unwrap().take() else {
+            return;
+        };
+        let token = self.token.clone();
+        thread::spawn(move || {
+            let mut request = ureq::delete(&resource);
+            if let Some(token) = &token {
+                request = request.set("Authorization", &format!("Bearer {}", token));
+            }
+            if let Err(err) = request.call() {
+                gstreamer::warning!(gstreamer::CAT_RUST, "Unable to delete the WHEP resource {} : {}", resource, err);
+            }
+        });
+    }
+}
+
+impl Drop for WhepResource {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
+
+/// The receive-only transceivers of the offer: VP8, VP9 or H264 video and Opus audio
+fn transceiver_caps() -> [Caps; 2] {
+    let video = ["VP8", "VP9", "H264"]
+        .iter()
+        .enumerate()
+        .map(|(i, encoding_name)| {
+            Structure::builder("application/x-rtp")
+                .field("media", "video")
+                .field("encoding-name", *encoding_name)
+                .field("payload", 96 + i as i32)
+                .field("clock-rate", 90000)
+                .build()
+        })
+        .collect::<Caps>();
+    let audio = Caps::builder("application/x-rtp")
+        .field("media", "audio")
+        .field("encoding-name", "OPUS")
+        .field("payload", 111)
+        .field("clock-rate", 48000)
+        .build();
+    [video, audio]
+}
+
+/// Create a webrtcbin receiving the stream of a WHEP endpoint. When the pipeline plays, the offer (with all the ICE
+/// candidates) is posted to `url` and the answer of the endpoint is applied. The received streams are the
+/// `application/x-rtp` pads added to the webrtcbin. A failed negotiation is an error of the webrtcbin.
+pub fn whep_source(
+    url: &str,
+    token: Option<&str>,
+    stun_server: Option<&str>,
+) -> Result<(Element, WhepSession), anyhow::Error> {
+    let webrtcbin = ElementFactory::make("webrtcbin").name(WHEP_WEBRTCBIN).build()?;
+    webrtcbin.set_property_from_str("bundle-policy", "max-bundle");
+    if let Some(stun_server) = stun_server {
+        webrtcbin.set_property("stun-server", stun_server);
+    }
+    for caps in transceiver_caps() {
+        webrtcbin.emit_by_name::<WebRTCRTPTransceiver>(
+            "add-transceiver",
+            &[&WebRTCRTPTransceiverDirection::Recvonly, &caps],
+        );
+    }
+
+    let session = WhepSession(Arc::new(WhepResource { url: Mutex::new(None), token: token.map(String::from) }));
+
+    webrtcbin.connect("on-negotiation-needed", false, |values| {
+        let webrtcbin = values[0].get::<Element>().expect("on-negotiation-needed without webrtcbin");
+        let webrtcbin_weak = webrtcbin.downgrade();
+        let promise = Promise::with_change_func(move |reply| {
+            let Some(webrtcbin) = webrtcbin_weak.upgrade() else {
+                return;
+            };
+            let offer = reply
+                .ok()
+                .flatten()
+                .and_then(|reply| reply.value("offer").ok())
+                .and_then(|offer| offer.get::<WebRTCSessionDescription>().ok());
+            match offer {
+                Some(offer) => webrtcbin.emit_by_name::<()>("set-local-description", &[&offer, &None::<Promise>]),
+                None => element_error!(webrtcbin, ResourceError::Failed, ("Unable to create the WebRTC offer")),
+            }
+        });
+        webrtcbin.emit_by_name::<()>("create-offer", &[&None::<Structure>, &promise]);
+        None
+    });
+
+    // no trickle ICE: the offer is posted with all the candidates
+    let posted = Arc::new(AtomicBool::new(false));
+    let url = url.to_string();
+    let post_session = session.clone();
+    webrtcbin.connect_notify(Some("ice-gathering-state"), move |webrtcbin, _| {
+        if webrtcbin.property::<WebRTCICEGatheringState>("ice-gathering-state") != WebRTCICEGatheringState::Complete
+            || posted.swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+        let Some(offer) = webrtcbin.property::<Option<WebRTCSessionDescription>>("local-description") else {
+            return;
+        };
+        let offer = offer.sdp().as_text().unwrap_or_default();
+        let webrtcbin_weak = webrtcbin.downgrade();
+        let url = url.clone();
+        let session = post_session.clone();
+        thread::spawn(move || {
+            let result = post_offer(&url, session.0.token.as_deref(), &offer);
+            let Some(webrtcbin) = webrtcbin_weak.upgrade() else {
+                return;
+            };
+            match result.and_then(|(answer, resource)| {
+                *session.0.url.lock().unwrap() = resource;
+                let sdp = SDPMessage::parse_buffer(answer.as_bytes())
+                    .map_err(|_| anyhow!("Invalid SDP answer from {}", url))?;
+                Ok(WebRTCSessionDescription::new(WebRTCSDPType::Answer, sdp))
+            }) {
+                Result::Ok(answer) => {
+                    webrtcbin.emit_by_name::<()>("set-remote-description", &[&answer, &None::<Promise>]);
+                }
+                Err(err) => {
+                    element_error!(webrtcbin, ResourceError::OpenRead, ("WHEP negotiation failed : {}", err));
+                }
+            }
+        });
+    });
+
+    // an ICE failure is an error so the player launches a new session
+    webrtcbin.connect_notify(Some("connection-state"), |webrtcbin, _| {
+        if webrtcbin.property::<WebRTCPeerConnectionState>("connection-state") == WebRTCPeerConnectionState::Failed {
+            element_error!(webrtcbin, ResourceError::Read, ("WebRTC connection failed"));
+        }
+    });
+    Ok((webrtcbin, session))
+}
+
+/// POST the SDP offer to the WHEP endpoint. Return the SDP answer and the url of the created resource.
+pub fn post_offer(url: &str, token: Option<&str>, offer: &str) -> Result<(String, Option<String>), anyhow::Error> {
+    let mut request = ureq::post(url).set("Content-Type", "application/sdp");
+    if let Some(token) = token {
+        request = request.set("Authorization", &format!("Bearer {}", token));
+    }
+    let response = request.send_string(offer).map_err(|err| anyhow!("{} refused the offer : {}", url, err))?;
+    if response.status() != 201 && response.status() != 200 {
+        return Err(anyhow!("{} answered {} {}", url, response.status(), response.status_text()));
+    }
+    let resource = response.header("Location").map(|location| resolve_uri(url, location));
+    let answer = response.into_string()?;
+    Ok((answer, resource))
+}
@@ -0,0 +1,223 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use gstreamer::{
+    prelude::{ElementExt, ObjectExt},
+    ClockTime, ElementFactory, MessageType, MessageView, State,
+};
+use gstreamer_app::AppSink;
+use gstreamer_video::{VideoCapsBuilder, VideoFormat, VideoInfo};
+use stereokit_rust_gstreamer::{
+    adaptive::{
+        connect_adaptive_demuxers, fetch_uri, is_manifest, parse_manifest, prefer_adaptivedemux2, resolve_uri,
+        AdaptiveStatus, Variant, ADAPTIVE_STATISTICS,
+    },
+    status::PlaybackStatus,
+};
+
+const MASTER: &str = "#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-STREAM-INF:BANDWIDTH=3000000,RESOLUTION=320x240,CODECS=\"avc1.42c01e\"
+high/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=300000,RESOLUTION=160x120,CODECS=\"avc1.42c00c\"
+low/index.m3u8
+";
+
+const MPD: &str = r#"<?xml version="1.0"?>
+<MPD xmlns="urn:mpeg:dash:schema:mpd:2011" type="static" mediaPresentationDuration="PT3S">
+  <Period>
+    <AdaptationSet mimeType="video/mp4" segmentAlignment="true">
+      <Representation id="720p" bandwidth="2500000" width="1280" height="720" codecs="avc1.4d401f"/>
+      <Representation id="360p" bandwidth="800000" width="640" height="360" codecs="avc1.4d401e"/>
+    </AdaptationSet>
+    <AdaptationSet mimeType="audio/mp4">
+      <Representation id="audio" bandwidth="128000" codecs="mp4a.40.2"/>
+    </AdaptationSet>
+  </Period>
+</MPD>"#;
+
+#[test]
+fn parse_hls_master_playlist() {
+    let variants = parse_manifest(MASTER, "http://host/streams/master.m3u8");
+    assert_eq!(
+        variants,
+        vec![
+            Variant {
+                uri: "http://host/streams/low/index.m3u8".into(),
+                bandwidth: 300000,
+                width: Some(160),
+                height: Some(120),
+                codecs: Some("avc1.42c00c".into()),
+            },
+            Variant {
+                uri: "http://host/streams/high/index.m3u8".into(),
+                bandwidth: 3000000,
+                width: Some(320),
+                height: Some(240),
+                codecs: Some("avc1.42c01e".into()),
+            },
+        ]
+    );
+    // a media playlist has no variant
+    assert!(parse_manifest("#EXTM3U\n#EXTINF:1.0,\nsegment00000.ts\n", "http://host/index.m3u8").is_empty());
+}
+
+#[test]
+fn parse_dash_representations() {
+    let variants = parse_manifest(MPD, "http://host/stream.mpd");
+    let ids: Vec<&str> = variants.iter().map(|variant| variant.uri.as_str()).collect();
+    assert_eq!(ids, vec!["360p", "720p"]);
+    assert_eq!(variants[1].bandwidth, 2500000);
+    assert_eq!((variants[1].width, variants[1].height), (Some(1280), Some(720)));
+    assert_eq!(variants[1].label(), "1280x720 2.5Mb/s");
+}
+
+#[test]
+fn manifest_uris() {
+    assert!(is_manifest("https://host/live/master.m3u8?token=1"));
+    assert!(is_manifest("/videos/stream.MPD"));
+    assert!(!is_manifest("https://host/video.webm"));
+    assert_eq!(resolve_uri("http://host/a/master.m3u8", "low/index.m3u8"), "http://host/a/low/index.m3u8");
+    assert_eq!(resolve_uri("http://host/a/master.m3u8", "/b/index.m3u8"), "http://host/b/index.m3u8");
+    assert_eq!(resolve_uri("http://host/a/master.m3u8", "https://cdn/index.m3u8"), "https://cdn/index.m3u8");
+}
+
+#[test]
+fn variant_selection() {
+    let mut adaptive =
+        AdaptiveStatus { variants: parse_manifest(MASTER, "http://host/master.m3u8"), ..Default::default() };
+    assert_eq!(adaptive.bitrate_range(), (0, 0));
+    adaptive.selected = Some(1);
+    assert_eq!(adaptive.bitrate_range(), (3000000, 3000000));
+
+    assert_eq!(adaptive.variant_of("http://host/low/index.m3u8", None), Some(0));
+    assert_eq!(adaptive.variant_of("http://host/high/segment00002.ts", None), Some(1));
+    assert_eq!(adaptive.variant_of("http://host/master.m3u8", None), None);
+    assert_eq!(adaptive.variant_of("http://host/master.m3u8", Some((160, 120))), Some(0));
+}
+
+/// Two H264 variants (160x120 and 320x240) of 3s in segments of 1s under low/ and high/, and their master playlist
+fn write_segments() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("stereokit_rust_gstreamer_hls_{}", std::process::id()));
+    for (name, width, height) in [("low", 160, 120), ("high", 320, 240)] {
+        let variant_dir = dir.join(name);
+        fs::create_dir_all(&variant_dir).unwrap();
+        let pipeline = gstreamer::parse::launch(&format!(
+            "videotestsrc num-buffers=90 ! video/x-raw,width={width},height={height},framerate=30/1 \
+             ! x264enc key-int-max=30 ! h264parse ! hlssink2 target-duration=1 max-files=0 playlist-length=0 \
+             location={dir}/segment%05d.ts playlist-location={dir}/index.m3u8",
+            dir = variant_dir.display()
+        ))
+        .unwrap();
+        pipeline.set_state(State::Playing).unwrap();
+        let bus = pipeline.bus().unwrap();
+        let msg = bus.timed_pop_filtered(ClockTime::from_seconds(30), &[MessageType::Eos, MessageType::Error]);
+        pipeline.set_state(State::Null).unwrap();
+        assert!(matches!(msg.as_ref().map(|msg| msg.view()), Some(MessageView::Eos(..))), "{:?}", msg);
+    }
+    fs::write(dir.join("master.m3u8"), MASTER).unwrap();
+    dir
+}
+
+/// A minimal http server of the files of `dir`, the stand-in of a CDN
+fn serve(dir: PathBuf) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request_line = String::new();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            if reader.read_line(&mut request_line).is_err() {
+                continue;
+            }
+            // skip the headers
+            let mut header = String::new();
+            while reader.read_line(&mut header).is_ok_and(|size| size > 2) {
+                header.clear();
+            }
+            let path = request_line.split_whitespace().nth(1).unwrap_or("/").trim_start_matches('/');
+            let response = match fs::read(dir.join(Path::new(path))) {
+                Ok(content) => {
+                    let content_type =
+                        if path.ends_with(".m3u8") { "application/vnd.apple.mpegurl" } else { "video/mp2t" };
+                    let mut response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        content_type,
+                        content.len()
+                    )
+                    .into_bytes();
+                    response.extend(content);
+                    response
+                }
+                Err(_) => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec(),
+            };
+            let _ = stream.write_all(&response);
+        }
+    });
+    url
+}
+
+#[test]
+fn hls_served_locally() {
+    gstreamer::init().unwrap();
+    prefer_adaptivedemux2();
+    let url = serve(write_segments());
+    let manifest_uri = format!("{}/master.m3u8", url);
+
+    let content = fetch_uri(&manifest_uri).unwrap();
+    let variants = parse_manifest(&String::from_utf8_lossy(&content), &manifest_uri);
+    assert_eq!(variants.len(), 2);
+
+    // the high variant is the first of the master playlist, the low one is selected
+    let status = Arc::new(Mutex::new(PlaybackStatus {
+        adaptive: Some(AdaptiveStatus { variants, selected: Some(0), ..Default::default() }),
+        ..Default::default()
+    }));
+    let appsink = AppSink::builder().caps(&VideoCapsBuilder::new().format(VideoFormat::Rgba).build()).build();
+    let playbin = ElementFactory::make("playbin3")
+        .property("uri", &manifest_uri)
+        .property("video-sink", &appsink)
+        .property("audio-sink", ElementFactory::make("fakesink").build().unwrap())
+        .build()
+        .unwrap();
+    connect_adaptive_demuxers(&playbin, status.clone());
+    playbin.set_state(State::Playing).unwrap();
+
+    let bus = playbin.bus().unwrap();
+    let mut played = None;
+    let mut downloaded = vec![];
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(10) && (played.is_none() || downloaded.is_empty()) {
+        if let Some(sample) = appsink.try_pull_sample(ClockTime::from_mseconds(100)) {
+            let info = VideoInfo::from_caps(sample.caps().unwrap()).unwrap();
+            if info.width() == 160 {
+                played = Some((info.width(), info.height()));
+            }
+        }
+        while let Some(msg) = bus.pop_filtered(&[MessageType::Element, MessageType::Error]) {
+            match msg.view() {
+                MessageView::Error(err) => panic!("{:?}", err),
+                MessageView::Element(element) => {
+                    if let Some(s) = element.structure().filter(|s| s.name() == ADAPTIVE_STATISTICS) {
+                        if let Ok(uri) = s.get::<String>("uri") {
+                            downloaded.push(uri);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    playbin.set_state(State::Null).unwrap();
+
+    assert_eq!(played, Some((160, 120)), "The selected variant has not been played");
+    let adaptive = status.lock().unwrap().adaptive.clone().unwrap();
+    assert!(downloaded.iter().any(|uri| adaptive.variant_of(uri, None) == Some(0)), "{:?}", downloaded);
+}
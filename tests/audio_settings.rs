@@ -0,0 +1,64 @@
+use gstreamer::{
+    prelude::{Cast, ElementExt, GstBinExtManual, GstObjectExt, ObjectExt},
+    Element, Pipeline,
+};
+use stereokit_rust_gstreamer::audio::{
+    apply_audio_settings, audio_effects, AudioSettings, EQUALIZER_MAX_GAIN, EQUALIZER_MIN_GAIN,
+};
+
+fn factory_name(element: &Element) -> String {
+    element.factory().unwrap().name().to_string()
+}
+
+fn bands(equalizer: &Element, count: usize) -> Vec<f64> {
+    (0..count).map(|band| equalizer.property::<f64>(&format!("band{}", band))).collect()
+}
+
+#[test]
+fn three_bands_equalizer() {
+    gstreamer::init().unwrap();
+    let mut settings = AudioSettings { volume: 2.0, mute: true, ..Default::default() };
+    settings.set_band(0, -6.0).unwrap();
+    settings.set_band(2, 3.0).unwrap();
+    assert!(settings.set_band(3, 1.0).is_err());
+
+    let [equalizer, volume] = audio_effects(&settings).unwrap();
+    assert_eq!(factory_name(&equalizer), "equalizer-3bands");
+    assert_eq!(bands(&equalizer, 3), [-6.0, 0.0, 3.0]);
+    assert_eq!(volume.property::<f64>("volume"), 2.0);
+    assert!(volume.property::<bool>("mute"));
+}
+
+#[test]
+fn ten_bands_equalizer() {
+    gstreamer::init().unwrap();
+    let gains: Vec<f64> = (0..10).map(|band| band as f64 - 5.0).collect();
+    let settings = AudioSettings { equalizer: gains.clone(), ..Default::default() };
+
+    let [equalizer, _volume] = audio_effects(&settings).unwrap();
+    assert_eq!(factory_name(&equalizer), "equalizer-10bands");
+    assert_eq!(bands(&equalizer, 10), gains);
+}
+
+#[test]
+fn gains_clamped() {
+    let mut settings = AudioSettings::default();
+    settings.set_band(0, 100.0).unwrap();
+    settings.set_band(1, -100.0).unwrap();
+    assert_eq!(settings.equalizer, [EQUALIZER_MAX_GAIN, EQUALIZER_MIN_GAIN, 0.0]);
+}
+
+#[test]
+fn settings_applied_to_a_running_pipeline() {
+    gstreamer::init().unwrap();
+    let [equalizer, volume] = audio_effects(&AudioSettings::default()).unwrap();
+    let pipeline = Pipeline::default();
+    pipeline.add_many([&equalizer, &volume]).unwrap();
+
+    // a 10 bands setting only changes the 3 bands of the equalizer created before
+    let settings = AudioSettings { volume: 20.0, mute: true, equalizer: vec![1.0; 10] };
+    apply_audio_settings(pipeline.upcast_ref(), &settings);
+    assert_eq!(bands(&equalizer, 3), [1.0; 3]);
+    assert_eq!(volume.property::<f64>("volume"), 10.0);
+    assert!(volume.property::<bool>("mute"));
+}
@@ -0,0 +1,102 @@
+use gstreamer::{glib::translate::IntoGlib, Rank, Registry};
+use stereokit_rust_gstreamer::{
+    decoder::DecoderResolver,
+    player::SinkContext,
+    probe::{
+        parse_rank, parse_rank_overrides, source_requirements, CapabilityReport, RankOverride, Requirements,
+        SourceSupport, ANDROID_RANK_OVERRIDES, PROBED_CODECS,
+    },
+};
+
+#[test]
+fn ranks() {
+    gstreamer::init().unwrap();
+    assert_eq!(parse_rank("primary").unwrap(), Rank::PRIMARY.into_glib());
+    assert_eq!(parse_rank("PRIMARY+1").unwrap(), Rank::PRIMARY.into_glib() + 1);
+    assert_eq!(parse_rank("marginal-10").unwrap(), Rank::MARGINAL.into_glib() - 10);
+    assert_eq!(parse_rank(" 300 ").unwrap(), 300);
+    assert!(parse_rank("best").is_err());
+    assert!(parse_rank("primary+x").is_err());
+
+    let overrides = parse_rank_overrides("# my decoders\n\nvah264dec primary+1\navdec_h264\t0\n").unwrap();
+    assert_eq!(
+        overrides,
+        vec![
+            RankOverride { factory: "vah264dec".into(), rank: Rank::PRIMARY.into_glib() + 1 },
+            RankOverride { factory: "avdec_h264".into(), rank: 0 },
+        ]
+    );
+    let err = parse_rank_overrides("vp8dec primary\nvp9dec\n").unwrap_err();
+    assert!(err.to_string().starts_with("Line 2"), "{}", err);
+    let err = parse_rank_overrides("vp8dec first").unwrap_err();
+    assert!(err.to_string().starts_with("Line 1 : Invalid rank first"), "{}", err);
+}
+
+#[test]
+fn android_overrides_name_features() {
+    gstreamer::init().unwrap();
+    let registry = Registry::get();
+    for rank_override in parse_rank_overrides(ANDROID_RANK_OVERRIDES).unwrap() {
+        // the MediaCodec decoders only exist on Android, but no entry may be the name of a plugin
+        let factory = rank_override.factory.as_str();
+        let plugin_only = registry.find_plugin(factory).is_some() && registry.lookup_feature(factory).is_none();
+        assert!(!plugin_only, "{} is a plugin, not an element", factory);
+        assert_eq!(rank_override.rank, Rank::PRIMARY.into_glib(), "{}", factory);
+    }
+}
+
+#[test]
+fn requirements() {
+    gstreamer::init().unwrap();
+    let decoders = DecoderResolver::default();
+    assert!(Requirements::new(&["videotestsrc"], &["video/x-vp8"]).missing(&decoders).is_empty());
+    assert_eq!(
+        Requirements::new(&["no-such-element", "videotestsrc"], &["video/x-no-such-codec"]).missing(&decoders),
+        vec!["no-such-element".to_string(), "decoder of video/x-no-such-codec".to_string()]
+    );
+    // a missing optional decoder doesn't block the launch
+    let optional =
+        Requirements::new(&["videotestsrc"], &[]).with_optional(&["audio/x-vorbis", "audio/x-no-such-codec"]);
+    assert!(optional.missing(&decoders).is_empty());
+    assert_eq!(optional.missing_optional(&decoders), vec!["decoder of audio/x-no-such-codec".to_string()]);
+
+    // the effects of the audio sinks are checked with the other sink elements
+    let sink_factories = SinkContext::factories();
+    for factory in ["queue", "appsink", "videoconvert", "audioresample", "equalizer-3bands", "equalizer-10bands"] {
+        assert!(sink_factories.contains(&factory), "{} not checked", factory);
+    }
+
+    let sources = source_requirements();
+    for name in ["RtpStream", "H264File", "Rtsp", "Srt", "Whep", "Device", "ScreenCapture", "TestPattern", "Playbin3"] {
+        assert!(sources.iter().any(|(source, _)| *source == name), "{} not probed", name);
+    }
+    let (_, rtp) = sources.iter().find(|(source, _)| *source == "RtpStream").unwrap();
+    assert!(rtp.elements.contains(&"rtph264depay"));
+    assert_eq!(rtp.decoded, vec!["video/x-h264"]);
+    let (_, h264) = sources.iter().find(|(source, _)| *source == "H264File").unwrap();
+    assert_eq!(h264.optional, vec!["audio/mpeg, mpegversion=(int)4"]);
+    let (_, vp9) = sources.iter().find(|(source, _)| *source == "VP9File").unwrap();
+    assert_eq!(vp9.optional, vec!["audio/x-opus", "audio/x-vorbis"]);
+}
+
+#[test]
+fn report() {
+    gstreamer::init().unwrap();
+    let report = CapabilityReport::probe(&DecoderResolver::default(), &source_requirements());
+    assert!(report.plugins.iter().any(|plugin| plugin == "coreelements"), "{:?}", report.plugins);
+    assert_eq!(report.codecs.len(), PROBED_CODECS.len());
+    let vp8 = report.codecs.iter().find(|codec| codec.codec == "VP8").unwrap();
+    assert!(vp8.label().starts_with("VP8 : ") && vp8.label().contains("vp8dec"), "{}", vp8.label());
+
+    let test_pattern = report.sources.iter().find(|source| source.name == "TestPattern").unwrap();
+    assert!(test_pattern.launchable(), "{}", test_pattern.label());
+    assert_eq!(test_pattern.label(), "TestPattern : ok");
+    let without =
+        SourceSupport { name: "H264File".into(), missing: vec![], without: vec!["decoder of audio/x-aac".into()] };
+    assert!(without.launchable());
+    assert_eq!(without.label(), "H264File : ok without decoder of audio/x-aac");
+
+    let lines = report.lines();
+    assert_eq!(lines.len(), 1 + report.codecs.len() + report.sources.len());
+    assert!(lines[0].contains("coreelements"));
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use std::{sync::Once, time::Duration};
+
+use common::receive_frames;
+use gstreamer::{glib, prelude::StaticType, Caps, DeviceProvider, Rank};
+use stereokit_rust_gstreamer::{
+    device::{device_modes, find_device, list_devices, DeviceMode, AUDIO_SOURCE_CLASS, VIDEO_SOURCE_CLASS},
+    video1::{VideoType, VideoTypeSource},
+};
+
+/// The caps of a usb webcam, the last structure (ranges) has no fixed mode
+const WEBCAM_CAPS: &str = "video/x-raw, format=(string)YUY2, width=(int)1280, height=(int)720, \
+    framerate=(fraction){ 10/1, 5/1 }; \
+    video/x-raw, format=(string)YUY2, width=(int)640, height=(int)480, framerate=(fraction){ 30/1, 15/1 }; \
+    image/jpeg, width=(int)1280, height=(int)720, framerate=(fraction)30/1; \
+    video/x-raw, format=(string)YUY2, width=(int)[ 1, 100 ], height=(int)[ 1, 100 ], framerate=(fraction)[ 0/1, 30/1 ]";
+
+/// The name of the camera listed by the fake device provider
+const FAKE_CAMERA: &str = "Fake camera";
+
+mod imp {
+    use std::sync::LazyLock;
+
+    use gstreamer::{
+        glib, prelude::*, subclass::prelude::*, subclass::DeviceProviderMetadata, Caps, Element, ElementFactory,
+        LoggableError,
+    };
+
+    /// A camera whose element is a live videotestsrc
+    #[derive(Default)]
+    pub struct FakeCamera;
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for FakeCamera {
+        const NAME: &'static str = "TestFakeCamera";
+        type Type = super::FakeCamera;
+        type ParentType = gstreamer::Device;
+    }
+
+    impl ObjectImpl for FakeCamera {}
+
+    impl GstObjectImpl for FakeCamera {}
+
+    impl DeviceImpl for FakeCamera {
+        fn create_element(&self, name: Option<&str>) -> Result<Element, LoggableError> {
+            let mut builder = ElementFactory::make("videotestsrc").property("is-live", true);
+            if let Some(name) = name {
+                builder = builder.name(name);
+            }
+            builder.build().map_err(|err| LoggableError::new(*gstreamer::CAT_RUST, err))
+        }
+    }
+
+    /// Lists one FakeCamera with a single mode
+    #[derive(Default)]
+    pub struct FakeCameraProvider;
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for FakeCameraProvider {
+        const NAME: &'static str = "TestFakeCameraProvider";
+        type Type = super::FakeCameraProvider;
+        type ParentType = gstreamer::DeviceProvider;
+    }
+
+    impl ObjectImpl for FakeCameraProvider {}
+
+    impl GstObjectImpl for FakeCameraProvider {}
+
+    impl DeviceProviderImpl for FakeCameraProvider {
+        fn metadata() -> Option<&'static DeviceProviderMetadata> {
+            static METADATA: LazyLock<DeviceProviderMetadata> = LazyLock::new(|| {
+                DeviceProviderMetadata::new("Fake cameras", "Source/Video", "videotestsrc as a camera", "")
+            });
+            Some(&*METADATA)
+        }
+
+        fn probe(&self) -> Vec<gstreamer::Device> {
+            let caps = "video/x-raw, format=(string)YUY2, width=(int)320, height=(int)240, framerate=(fraction)15/1";
+            let camera = glib::Object::builder::<super::FakeCamera>()
+                .property("display-name", super::FAKE_CAMERA)
+                .property("device-class", "Video/Source")
+                .property("caps", caps.parse::<Caps>().unwrap())
+                .build();
+            vec![camera.upcast()]
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct FakeCamera(ObjectSubclass<imp::FakeCamera>) @extends gstreamer::Device, gstreamer::Object;
+}
+
+glib::wrapper! {
+    pub struct FakeCameraProvider(ObjectSubclass<imp::FakeCameraProvider>)
+        @extends gstreamer::DeviceProvider, gstreamer::Object;
+}
+
+/// Let the device monitor list the fake camera
+fn register_fake_camera() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        gstreamer::init().unwrap();
+        DeviceProvider::register(None, "fakecameraprovider", Rank::PRIMARY, FakeCameraProvider::static_type()).unwrap();
+    });
+}
+
+#[test]
+fn webcam_modes() {
+    gstreamer::init().unwrap();
+    let modes = device_modes(&WEBCAM_CAPS.parse::<Caps>().unwrap());
+    let labels: Vec<String> = modes.iter().map(DeviceMode::label).collect();
+    assert_eq!(
+        labels,
+        vec![
+            "1280x720 30fps image/jpeg",
+            "1280x720 10fps YUY2",
+            "1280x720 5fps YUY2",
+            "640x480 30fps YUY2",
+            "640x480 15fps YUY2"
+        ]
+    );
+    let caps = modes[3].caps();
+    assert!(caps.is_fixed());
+    assert!(caps.can_intersect(&WEBCAM_CAPS.parse::<Caps>().unwrap()));
+}
+
+#[test]
+fn fake_camera_mode() {
+    register_fake_camera();
+    let camera = find_device(FAKE_CAMERA, true).expect("The fake camera is not listed");
+    let mode = camera.modes.first().cloned().expect("The fake camera has no mode");
+    assert_eq!(mode.label(), "320x240 15fps YUY2");
+
+    // the mode is forced by the capsfilter of the capture bin, the camera could deliver any size
+    let camera = Some(FAKE_CAMERA.to_string());
+    let source = VideoTypeSource::new(VideoType::Device { camera, mode: Some(mode), microphone: None });
+    let (mut driver, status) = receive_frames(source, 1);
+    assert!(status.frames > 0, "No frame from the fake camera : {:?}", status);
+    assert_eq!(status.video_size, Some((320, 240)));
+
+    driver.run_for(Duration::from_secs(2));
+    let frames = driver.status().frames - status.frames;
+    assert!((15..=45).contains(&frames), "{} frames in 2s at 15fps", frames);
+}
+
+#[test]
+fn device_monitor() {
+    gstreamer::init().unwrap();
+    // the devices of this machine (a v4l2loopback is a camera), there may be none
+    for device in list_devices().unwrap() {
+        assert!(
+            device.class.starts_with(VIDEO_SOURCE_CLASS) || device.class.starts_with(AUDIO_SOURCE_CLASS),
+            "{:?}",
+            device
+        );
+        assert!(!device.name.is_empty());
+        if device.is_video() {
+            device.create_element().unwrap();
+        }
+    }
+}
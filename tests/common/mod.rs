@@ -0,0 +1,252 @@
+//! Fixtures shared by the integration tests: generated media, local senders and a Driver stepping the Playback of a
+//! source the way the stepper does, with offscreen sinks (no StereoKit).
+#![allow(dead_code)]
+
+use std::{
+    net::UdpSocket,
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use gstreamer::{
+    glib::{MainContext, MainLoop},
+    prelude::{Cast, ElementExt, GstBinExt, GstObjectExt},
+    Bin, ClockTime, Element, MessageType, MessageView, State, Structure,
+};
+use gstreamer_rtsp_server::{
+    prelude::{
+        RTSPAuthExt, RTSPMediaFactoryExt, RTSPMediaFactoryExtManual, RTSPMountPointsExt, RTSPServerExt,
+        RTSPServerExtManual,
+    },
+    RTSPAuth, RTSPFilterResult, RTSPMediaFactory, RTSPServer, RTSPToken, RTSP_PERM_MEDIA_FACTORY_ACCESS,
+    RTSP_PERM_MEDIA_FACTORY_CONSTRUCT, RTSP_TOKEN_MEDIA_FACTORY_ROLE,
+};
+use stereokit_rust_gstreamer::{
+    playback::Playback,
+    player::{SinkContext, SourceStrategy},
+    status::{PlaybackStatus, StreamState},
+    video1::{VideoType, VideoTypeSource},
+};
+
+pub const WIDTH: i32 = 320;
+pub const HEIGHT: i32 = 240;
+
+/// 30 frames of VP8 with Vorbis in a matroska file
+pub const VP8_MKV: &str = "videotestsrc num-buffers=30 ! video/x-raw,width=160,height=120,framerate=30/1 \
+    ! vp8enc deadline=1 ! mux. audiotestsrc num-buffers=40 ! audioconvert ! vorbisenc ! mux. matroskamux name=mux";
+
+/// Ask the OS for a free udp port on the loopback
+pub fn free_port() -> i32 {
+    let socket = UdpSocket::bind("127.0.0.1:0").expect("no free udp port");
+    socket.local_addr().unwrap().port() as i32
+}
+
+/// The element built by `factory` in a pipeline (the first one)
+pub fn element_of(pipeline: &Element, factory: &str) -> Option<Element> {
+    let bin = pipeline.downcast_ref::<Bin>()?;
+    bin.iterate_recurse()
+        .into_iter()
+        .flatten()
+        .find(|element| element.factory().is_some_and(|f| f.name() == factory))
+}
+
+/// A local rtpvrawpay sender of a live test pattern (RGBA, I420, ...) losing `drop_probability` of its packets.
+/// Multicast packets are looped back to the local receivers. It stops when dropped.
+pub struct RtpRawSender(Element);
+
+impl RtpRawSender {
+    pub fn start(host: &str, port: i32, format: &str, size: (i32, i32), drop_probability: f32) -> Self {
+        gstreamer::init().unwrap();
+        let (width, height) = size;
+        let sender = gstreamer::parse::launch(&format!(
+            "videotestsrc is-live=true pattern=smpte \
+             ! video/x-raw,format={format},width={width},height={height},framerate=30/1 \
+             ! rtpvrawpay ! identity drop-probability={drop_probability} \
+             ! udpsink host={host} port={port} auto-multicast=true loop=true ttl-mc=0"
+        ))
+        .unwrap();
+        sender.set_state(State::Playing).unwrap();
+        Self(sender)
+    }
+}
+
+impl Drop for RtpRawSender {
+    fn drop(&mut self) {
+        let _ = self.0.set_state(State::Null);
+    }
+}
+
+/// The RtpRawStream source of a Video1 on `port`, the size of the frames comes from the sinks
+pub fn rtp_raw_video(port: i32, sampling: &str) -> VideoTypeSource {
+    let mut source = VideoTypeSource::new(VideoType::RtpRawStream { port });
+    source.sampling = sampling.into();
+    source
+}
+
+/// Play a RtpRawStream source with sinks of `size` (the size of the frames of the sender)
+pub fn start_rtp_raw(source: VideoTypeSource, size: (i32, i32)) -> Result<Driver<VideoTypeSource>, anyhow::Error> {
+    Driver::start_with(source, SinkContext::offscreen(size.0, size.1, false))
+}
+
+/// Run `description ! filesink` to the end and return the path of the file (in the temp dir)
+pub fn encoded_file(name: &str, description: &str) -> PathBuf {
+    gstreamer::init().unwrap();
+    let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+    let description = format!("{} ! filesink location={}", description, path.display());
+    let pipeline = gstreamer::parse::launch(&description).unwrap();
+    pipeline.set_state(State::Playing).unwrap();
+    let bus = pipeline.bus().unwrap();
+    let msg = bus.timed_pop_filtered(ClockTime::from_seconds(30), &[MessageType::Eos, MessageType::Error]);
+    pipeline.set_state(State::Null).unwrap();
+    match msg.as_ref().map(|msg| msg.view()) {
+        Some(MessageView::Eos(..)) => path,
+        otherwise => panic!("Unable to write {} : {:?}", path.display(), otherwise),
+    }
+}
+
+/// Play `source` with sinks keeping the size of the frames until `frames` are received or the stream reports an
+/// error (10s at most). The driver keeps the pipeline playing, or reconnecting.
+pub fn receive_frames<S: SourceStrategy>(source: S, frames: u64) -> (Driver<S>, PlaybackStatus) {
+    let mut driver = Driver::start_with(source, SinkContext::offscreen(0, 0, true)).unwrap();
+    driver.run_until(Duration::from_secs(10), |status| status.frames >= frames || status.error.is_some());
+    let status = driver.status();
+    (driver, status)
+}
+
+/// A local rtsp server with a jpeg test pattern of WIDTH x HEIGHT on /test, the stand-in of an IP camera. Its main
+/// loop runs until it is dropped.
+pub struct RtspTestServer {
+    pub url: String,
+    server: RTSPServer,
+    main_loop: MainLoop,
+}
+
+impl RtspTestServer {
+    /// A server asking for these credentials if any
+    pub fn start(credentials: Option<(&str, &str)>) -> Self {
+        gstreamer::init().unwrap();
+        let server = RTSPServer::new();
+        server.set_service("0");
+        let mounts = server.mount_points().unwrap();
+        let factory = RTSPMediaFactory::new();
+        factory.set_launch(&format!(
+            "( videotestsrc is-live=true pattern=smpte ! video/x-raw,width={WIDTH},height={HEIGHT},framerate=30/1 \
+             ! jpegenc ! rtpjpegpay name=pay0 pt=96 )"
+        ));
+        factory.set_shared(true);
+
+        if let Some((user, password)) = credentials {
+            let auth = RTSPAuth::new();
+            let token = RTSPToken::builder().field(RTSP_TOKEN_MEDIA_FACTORY_ROLE, user).build();
+            let basic = RTSPAuth::make_basic(user, password);
+            auth.add_basic(basic.as_str(), &token);
+            server.set_auth(Some(&auth));
+            factory.add_role_from_structure(
+                &Structure::builder(user)
+                    .field(RTSP_PERM_MEDIA_FACTORY_ACCESS, true)
+                    .field(RTSP_PERM_MEDIA_FACTORY_CONSTRUCT, true)
+                    .build(),
+            );
+        }
+        mounts.add_factory("/test", factory);
+
+        let context = MainContext::new();
+        server.attach(Some(&context)).unwrap();
+        let url = format!("rtsp://127.0.0.1:{}/test", server.bound_port());
+
+        let main_loop = MainLoop::new(Some(&context), false);
+        let server_loop = main_loop.clone();
+        thread::spawn(move || server_loop.run());
+        Self { url, server, main_loop }
+    }
+
+    /// Close the connections of the clients, as a camera that reboots. The new clients are served.
+    pub fn close_clients(&self) {
+        self.server.client_filter(Some(&mut |_server, _client| RTSPFilterResult::Remove));
+    }
+}
+
+impl Drop for RtspTestServer {
+    fn drop(&mut self) {
+        self.main_loop.quit();
+    }
+}
+
+/// Steps the Playback of a source like MediaPlayer::step(), with offscreen sinks: the states of the status, the
+/// end of the stream, the decoder fallback and the reconnections are the ones of the shipped player. The messages
+/// waiting on the bus are all handled at each step and the states they give are recorded.
+pub struct Driver<S: SourceStrategy> {
+    pub playback: Playback<S>,
+    /// The successive states of the status, without repetition
+    pub states: Vec<StreamState>,
+}
+
+impl<S: SourceStrategy> Driver<S> {
+    /// Build and play the pipeline of `source`, its frames are scaled to WIDTH x HEIGHT
+    pub fn start(source: S) -> Result<Self, anyhow::Error> {
+        Self::start_with(source, SinkContext::offscreen(WIDTH, HEIGHT, false))
+    }
+
+    pub fn start_with(source: S, sinks: SinkContext) -> Result<Self, anyhow::Error> {
+        gstreamer::init()?;
+        let mut playback = Playback::new(source);
+        playback.start("Driver", sinks)?;
+        playback.play()?;
+        Ok(Self { playback, states: vec![StreamState::Idle] })
+    }
+
+    /// The running pipeline (None after an error or while reconnecting)
+    pub fn pipeline(&self) -> Option<&Element> {
+        self.playback.pipeline()
+    }
+
+    pub fn status(&self) -> PlaybackStatus {
+        self.playback.get_status()
+    }
+
+    /// Step every 10ms until `done` or the timeout. true if done.
+    pub fn run_until(&mut self, timeout: Duration, done: impl Fn(&PlaybackStatus) -> bool) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            thread::sleep(Duration::from_millis(10));
+            self.step();
+            if done(&self.status()) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Did the status go through these states in this order (other states may be between them)
+    pub fn went_through(&self, expected: &[StreamState]) -> bool {
+        let mut states = self.states.iter();
+        expected.iter().all(|state| states.any(|s| s == state))
+    }
+
+    /// Step for `duration` whatever happens
+    pub fn run_for(&mut self, duration: Duration) {
+        self.run_until(duration, |_| false);
+    }
+
+    fn step(&mut self) {
+        self.playback.step();
+        self.record_state();
+        while self.playback.check_bus() {
+            self.record_state();
+        }
+    }
+
+    fn record_state(&mut self) {
+        let state = self.status().state;
+        if self.states.last() != Some(&state) {
+            self.states.push(state);
+        }
+    }
+}
+
+impl<S: SourceStrategy> Drop for Driver<S> {
+    fn drop(&mut self) {
+        self.playback.stop();
+    }
+}
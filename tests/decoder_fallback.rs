@@ -0,0 +1,139 @@
+//! A decoder of the highest rank that fails on the first buffer: the source excludes it after its error and the next
+//! pipeline plays with the next candidate, whether the source resolves its decoders or lets decodebin plug them.
+
+mod common;
+
+use std::{sync::Once, time::Duration};
+
+use common::{encoded_file, Driver, VP8_MKV};
+use gstreamer::{
+    glib::{
+        self,
+        translate::{FromGlib, IntoGlib},
+    },
+    prelude::StaticType,
+    Rank,
+};
+use stereokit_rust_gstreamer::video1::{VideoType, VideoTypeSource};
+
+const FAILDEC: &str = "faildec";
+
+mod imp {
+    use std::sync::LazyLock;
+
+    use gstreamer::{glib, prelude::*, subclass::prelude::*, Buffer, Caps, FlowError, FlowSuccess, Pad};
+
+    /// Accepts VP8 and posts a decoding error on the first buffer
+    pub struct FailDec {
+        sinkpad: Pad,
+        srcpad: Pad,
+    }
+
+    impl FailDec {
+        fn chain(&self, _buffer: Buffer) -> Result<FlowSuccess, FlowError> {
+            gstreamer::element_imp_error!(self, gstreamer::StreamError::Decode, ["Unable to decode"]);
+            Err(FlowError::Error)
+        }
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for FailDec {
+        const NAME: &'static str = "TestFailDec";
+        type Type = super::FailDec;
+        type ParentType = gstreamer::Element;
+
+        fn with_class(klass: &Self::Class) -> Self {
+            let sinkpad = Pad::builder_from_template(&klass.pad_template("sink").unwrap())
+                .chain_function(|_pad, parent, buffer| {
+                    FailDec::catch_panic_pad_function(parent, || Err(FlowError::Error), |faildec| faildec.chain(buffer))
+                })
+                .build();
+            let srcpad = Pad::builder_from_template(&klass.pad_template("src").unwrap()).build();
+            Self { sinkpad, srcpad }
+        }
+    }
+
+    impl ObjectImpl for FailDec {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().add_pad(&self.sinkpad).unwrap();
+            self.obj().add_pad(&self.srcpad).unwrap();
+        }
+    }
+
+    impl GstObjectImpl for FailDec {}
+
+    impl ElementImpl for FailDec {
+        fn metadata() -> Option<&'static gstreamer::subclass::ElementMetadata> {
+            static METADATA: LazyLock<gstreamer::subclass::ElementMetadata> = LazyLock::new(|| {
+                gstreamer::subclass::ElementMetadata::new("Failing decoder", "Codec/Decoder/Video", "Always fails", "")
+            });
+            Some(&*METADATA)
+        }
+
+        fn pad_templates() -> &'static [gstreamer::PadTemplate] {
+            static PAD_TEMPLATES: LazyLock<Vec<gstreamer::PadTemplate>> = LazyLock::new(|| {
+                let sink = gstreamer::PadTemplate::new(
+                    "sink",
+                    gstreamer::PadDirection::Sink,
+                    gstreamer::PadPresence::Always,
+                    &Caps::new_empty_simple("video/x-vp8"),
+                )
+                .unwrap();
+                let src = gstreamer::PadTemplate::new(
+                    "src",
+                    gstreamer::PadDirection::Src,
+                    gstreamer::PadPresence::Always,
+                    &Caps::new_empty_simple("video/x-raw"),
+                )
+                .unwrap();
+                vec![sink, src]
+            });
+            PAD_TEMPLATES.as_ref()
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct FailDec(ObjectSubclass<imp::FailDec>) @extends gstreamer::Element, gstreamer::Object;
+}
+
+/// Register faildec above all the VP8 decoders
+fn register_faildec() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| {
+        gstreamer::init().unwrap();
+        // any i32 is a valid GstRank
+        let rank = unsafe { Rank::from_glib(Rank::PRIMARY.into_glib() + 1000) };
+        gstreamer::Element::register(None, FAILDEC, rank, FailDec::static_type()).unwrap();
+    });
+}
+
+/// Play `source` until frames are decoded, faildec must have been excluded for the next decoder
+fn falls_back(mut source: VideoTypeSource) {
+    source.decoders.hardware_first = false;
+    let mut driver = Driver::start(source).unwrap();
+    let played = driver.run_until(Duration::from_secs(10), |status| status.frames > 0);
+    let status = driver.status();
+    assert!(played, "{:?}", status);
+    assert_eq!(driver.playback.source.decoders.excluded, vec![FAILDEC.to_string()]);
+    let plugged = driver.playback.source.decoders.plugged();
+    assert!(!plugged.is_empty() && !plugged.contains(&FAILDEC.to_string()), "{:?}", plugged);
+    assert!(status.error.is_none(), "{:?}", status.error);
+}
+
+#[test]
+fn resolved_decoder_falls_back() {
+    register_faildec();
+    let uri = encoded_file("fallback.mkv", VP8_MKV).to_string_lossy().to_string();
+    let mut source = VideoTypeSource::new(VideoType::VP8File { uri });
+    source.decoders.preferred = vec![FAILDEC.into()];
+    falls_back(source);
+}
+
+#[test]
+fn decodebin_skips_the_excluded_decoder() {
+    register_faildec();
+    let uri = encoded_file("fallback-decodebin.mkv", VP8_MKV).to_string_lossy().to_string();
+    falls_back(VideoTypeSource::new(VideoType::Decodebin { uri, v3_enabled: false }));
+}
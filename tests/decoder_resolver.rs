@@ -0,0 +1,78 @@
+use gstreamer::{
+    prelude::{Cast, ElementExt, GstBinExtManual, GstObjectExt},
+    Caps, ClockTime, Element, ElementFactory, Pipeline, State,
+};
+use gstreamer_app::AppSink;
+use gstreamer_video::{VideoCapsBuilder, VideoFormat, VideoInfo};
+use stereokit_rust_gstreamer::decoder::DecoderResolver;
+
+fn vp8() -> Caps {
+    Caps::new_empty_simple("video/x-vp8")
+}
+
+#[test]
+fn candidates_order() {
+    gstreamer::init().unwrap();
+    let resolver = DecoderResolver::default();
+    let candidates = resolver.candidates(&vp8());
+    assert!(candidates.iter().any(|candidate| candidate.name == "vp8dec"), "{:?}", candidates);
+
+    // the hardware decoders first, then by rank
+    for pair in candidates.windows(2) {
+        assert!(pair[0].hardware >= pair[1].hardware, "{:?}", candidates);
+        if pair[0].hardware == pair[1].hardware {
+            assert!(pair[0].rank >= pair[1].rank, "{:?}", candidates);
+        }
+    }
+    // the registry never gives an audio decoder for a video stream
+    assert!(resolver.candidates(&Caps::new_empty_simple("audio/x-opus")).iter().all(|c| c.name != "vp8dec"));
+}
+
+#[test]
+fn preferred_and_excluded() {
+    gstreamer::init().unwrap();
+    let resolver = DecoderResolver { preferred: vec!["vp8dec".into()], hardware_first: true, ..Default::default() };
+    assert_eq!(resolver.candidates(&vp8())[0].name, "vp8dec");
+
+    let mut resolver = DecoderResolver::default();
+    assert!(resolver.exclude("vp8dec"));
+    assert!(!resolver.exclude("vp8dec"), "excluded twice");
+    assert!(!resolver.exclude("videotestsrc"), "not a decoder");
+    assert!(resolver.candidates(&vp8()).iter().all(|candidate| candidate.name != "vp8dec"));
+    // without vp8dec, the next candidate is used or the error tells what has been tried
+    match resolver.resolve(&vp8()) {
+        Ok((_, candidate)) => assert_ne!(candidate.name, "vp8dec"),
+        Err(err) => assert!(err.to_string().contains("No decoder for video/x-vp8"), "{}", err),
+    }
+}
+
+#[test]
+fn no_decoder() {
+    gstreamer::init().unwrap();
+    let err = DecoderResolver::default().resolve_media_type("video/x-no-such-codec").unwrap_err();
+    assert!(err.to_string().starts_with("No decoder for video/x-no-such-codec"), "{}", err);
+}
+
+#[test]
+fn resolved_decoder_decodes() {
+    gstreamer::init().unwrap();
+    let (decoder, candidate) = DecoderResolver::default().resolve(&vp8()).unwrap();
+    assert_eq!(decoder.factory().unwrap().name(), candidate.name.as_str());
+
+    let pipeline = Pipeline::default();
+    let source = gstreamer::parse::bin_from_description(
+        "videotestsrc num-buffers=10 ! video/x-raw,width=160,height=120 ! vp8enc deadline=1",
+        true,
+    )
+    .unwrap();
+    let convert = ElementFactory::make("videoconvert").build().unwrap();
+    let appsink = AppSink::builder().caps(&VideoCapsBuilder::new().format(VideoFormat::Rgba).build()).build();
+    pipeline.add_many([source.upcast_ref(), &decoder, &convert, appsink.upcast_ref()]).unwrap();
+    Element::link_many([source.upcast_ref(), &decoder, &convert, appsink.upcast_ref()]).unwrap();
+    pipeline.set_state(State::Playing).unwrap();
+
+    let sample = appsink.try_pull_sample(ClockTime::from_seconds(5));
+    pipeline.set_state(State::Null).unwrap();
+    let info = VideoInfo::from_caps(sample.expect("Nothing decoded").caps().unwrap()).unwrap();
+    assert_eq!((info.width(), info.height()), (160, 120));
+}
@@ -0,0 +1,317 @@
+//! Launch StereoKit offscreen and check how the Video1 screens behave while something happens to their stream: a file
+//! played to its end, a file looping, a playlist of two files, a RTSP camera closing its connections. Each scenario
+//! has its own screen and they are stepped together.
+//! The event loop must run on the main thread, so this test has no harness: `cargo test --test headless_player`
+
+mod common;
+
+use std::{thread, time::Duration};
+
+use common::{encoded_file, RtspTestServer, VP8_MKV};
+use gstreamer::ClockTime;
+use stereokit_rust::{
+    event_loop::{SkClosures, StepperAction},
+    sk::{AppMode, Sk, SkSettings},
+    system::LogLevel,
+};
+use stereokit_rust_gstreamer::{
+    probe::gstreamer_init,
+    rtsp::RtspTransport,
+    status::{PlaybackStatus, PlaybackStatusHandle, StreamState},
+    video1::{Video1, VideoType},
+};
+
+/// Frames stepped at most (10ms each) before giving up
+const MAX_FRAMES: u32 = 3000;
+const WIDTH: i32 = 64;
+const HEIGHT: i32 = 36;
+/// The same media as VP8_MKV lasting 2s
+const VP8_MKV_2S: &str = "videotestsrc num-buffers=60 ! video/x-raw,width=160,height=120,framerate=30/1 \
+    ! vp8enc deadline=1 ! mux. audiotestsrc num-buffers=80 ! audioconvert ! vorbisenc ! mux. matroskamux name=mux";
+
+/// Something happening to a screen
+trait Scenario {
+    /// Called at each frame, true when the scenario is over
+    fn step(&mut self, sk: &mut Sk) -> bool;
+    /// Panic if the screen didn't behave
+    fn check(&self);
+}
+
+/// A screen added to StereoKit and the successive states of its status, without repetition
+#[derive(Debug)]
+struct Screen {
+    id: &'static str,
+    status: PlaybackStatusHandle,
+    states: Vec<StreamState>,
+}
+
+impl Screen {
+    fn add(sk: &mut Sk, id: &'static str, mut video: Video1) -> Self {
+        video.width = WIDTH;
+        video.height = HEIGHT;
+        let status = video.status_handle();
+        sk.push_action(StepperAction::add(id, video));
+        Self { id, status, states: vec![] }
+    }
+
+    /// Read the status and record its state
+    fn observe(&mut self) -> PlaybackStatus {
+        let status = self.status.lock().unwrap().clone();
+        if self.states.last() != Some(&status.state) {
+            self.states.push(status.state);
+        }
+        status
+    }
+
+    /// Did the screen go through these states in this order (other states may be between them)
+    fn went_through(&self, expected: &[StreamState]) -> bool {
+        let mut states = self.states.iter();
+        expected.iter().all(|state| states.any(|s| s == state))
+    }
+}
+
+/// A short file played to its end: the position advances and the states follow the pipeline
+struct PlaybackToEos {
+    screen: Screen,
+    /// The successive positions, without repetition
+    positions: Vec<ClockTime>,
+    duration: Option<ClockTime>,
+    progress: Option<f32>,
+}
+
+impl PlaybackToEos {
+    fn new(sk: &mut Sk) -> Self {
+        let uri = encoded_file("status.mkv", VP8_MKV).to_string_lossy().to_string();
+        let screen = Screen::add(sk, "PlaybackToEos", Video1::new(VideoType::VP8File { uri }));
+        Self { screen, positions: vec![], duration: None, progress: None }
+    }
+}
+
+impl Scenario for PlaybackToEos {
+    fn step(&mut self, _sk: &mut Sk) -> bool {
+        let status = self.screen.observe();
+        if let Some(position) = status.position {
+            if self.positions.last() != Some(&position) {
+                self.positions.push(position);
+            }
+        }
+        self.duration = self.duration.or(status.duration);
+        self.progress = status.progress().or(self.progress);
+        status.state == StreamState::Eos
+    }
+
+    fn check(&self) {
+        let states = &self.screen.states;
+        let expected = [StreamState::Idle, StreamState::Prerolled, StreamState::Playing, StreamState::Eos];
+        assert!(self.screen.went_through(&expected), "{} : {:?}", self.screen.id, states);
+        assert_eq!(states.first(), Some(&StreamState::Idle), "{} : {:?}", self.screen.id, states);
+        assert_eq!(states.last(), Some(&StreamState::Eos), "{} : {:?}", self.screen.id, states);
+        assert!(!states.contains(&StreamState::Error), "{} : {:?}", self.screen.id, states);
+
+        // 30 frames at 30fps
+        let duration = self.duration.expect("No duration");
+        assert!((900..=1100).contains(&duration.mseconds()), "{} : duration {}", self.screen.id, duration);
+        assert!(self.positions.len() >= 3, "{} : the position didn't advance {:?}", self.screen.id, self.positions);
+        assert!(self.positions.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", self.positions);
+        assert!(
+            self.progress.is_some_and(|progress| progress > 0.5),
+            "{} : progress {:?}",
+            self.screen.id,
+            self.progress
+        );
+    }
+}
+
+/// A short file played with `looping`: the segment seeks bring the position back to the start without EOS and, once
+/// the first loop is done, without leaving Playing
+struct SeamlessLoop {
+    screen: Screen,
+    last_position: Option<ClockTime>,
+    /// Number of states recorded when the position came back to the start
+    loops: Vec<usize>,
+}
+
+impl SeamlessLoop {
+    const LOOPS: usize = 3;
+
+    fn new(sk: &mut Sk) -> Self {
+        let uri = encoded_file("loop.mkv", VP8_MKV).to_string_lossy().to_string();
+        let mut video = Video1::new(VideoType::VP8File { uri });
+        video.playback.looping = true;
+        let screen = Screen::add(sk, "SeamlessLoop", video);
+        Self { screen, last_position: None, loops: vec![] }
+    }
+}
+
+impl Scenario for SeamlessLoop {
+    fn step(&mut self, _sk: &mut Sk) -> bool {
+        let status = self.screen.observe();
+        if let Some(position) = status.position {
+            if self.last_position.is_some_and(|last| last > position + ClockTime::from_mseconds(300)) {
+                self.loops.push(self.screen.states.len());
+            }
+            self.last_position = Some(position);
+        }
+        self.loops.len() >= Self::LOOPS || matches!(status.state, StreamState::Eos | StreamState::Error)
+    }
+
+    fn check(&self) {
+        let states = &self.screen.states;
+        assert!(
+            self.loops.len() >= Self::LOOPS,
+            "{} looped {} times : {:?}",
+            self.screen.id,
+            self.loops.len(),
+            states
+        );
+        assert!(!states.contains(&StreamState::Eos), "{} : {:?}", self.screen.id, states);
+        assert!(!states.contains(&StreamState::Error), "{} : {:?}", self.screen.id, states);
+        // SegmentDone then a non flushing segment seek: the pipeline doesn't change its state
+        let after_first_loop = &states[self.loops[0] - 1..];
+        assert_eq!(after_first_loop, &[StreamState::Playing], "{} : {:?}", self.screen.id, states);
+    }
+}
+
+/// A playlist of a 1s file and a 2s file: the second one is played at the end of the first one, then the screen
+/// reaches EOS
+struct PlaylistAdvance {
+    screen: Screen,
+    /// The successive durations, without repetition
+    durations: Vec<ClockTime>,
+    max_position: Option<ClockTime>,
+}
+
+impl PlaylistAdvance {
+    fn new(sk: &mut Sk) -> Self {
+        let first = encoded_file("playlist1.mkv", VP8_MKV).to_string_lossy().to_string();
+        let second = encoded_file("playlist2.mkv", VP8_MKV_2S).to_string_lossy().to_string();
+        let mut video = Video1::new(VideoType::VP8File { uri: first.clone() });
+        video.playback.playlist = vec![first, second];
+        let screen = Screen::add(sk, "PlaylistAdvance", video);
+        Self { screen, durations: vec![], max_position: None }
+    }
+}
+
+impl Scenario for PlaylistAdvance {
+    fn step(&mut self, _sk: &mut Sk) -> bool {
+        let status = self.screen.observe();
+        if let Some(duration) = status.duration {
+            if self.durations.last() != Some(&duration) {
+                self.durations.push(duration);
+            }
+        }
+        self.max_position = self.max_position.max(status.position);
+        matches!(status.state, StreamState::Eos | StreamState::Error)
+    }
+
+    fn check(&self) {
+        let states = &self.screen.states;
+        assert_eq!(states.last(), Some(&StreamState::Eos), "{} : {:?}", self.screen.id, states);
+        assert!(!states.contains(&StreamState::Error), "{} : {:?}", self.screen.id, states);
+
+        let durations: Vec<u64> = self.durations.iter().map(|duration| duration.mseconds()).collect();
+        let first = durations.iter().position(|duration| (900..=1100).contains(duration));
+        let second = durations.iter().rposition(|duration| (1900..=2100).contains(duration));
+        assert!(first.is_some() && first < second, "{} : durations {:?}", self.screen.id, durations);
+        assert!(
+            self.max_position.is_some_and(|position| position.mseconds() > 1500),
+            "{} : the second file wasn't played, position {:?}",
+            self.screen.id,
+            self.max_position
+        );
+    }
+}
+
+/// A RTSP camera closing the connection while the screen plays: the screen shows Reconnecting then plays again with a
+/// new pipeline
+struct RtspReconnection {
+    server: RtspTestServer,
+    screen: Screen,
+    /// Frames received when the connection was closed
+    frames_at_loss: Option<u64>,
+    frames: u64,
+}
+
+impl RtspReconnection {
+    fn new(sk: &mut Sk) -> Self {
+        let server = RtspTestServer::start(None);
+        let url = server.url.clone();
+        let mut video = Video1::new(VideoType::Rtsp { url, latency: 200, transport: RtspTransport::Tcp });
+        video.playback.source.reconnect_delay = 0.5;
+        let screen = Screen::add(sk, "Rtsp", video);
+        Self { server, screen, frames_at_loss: None, frames: 0 }
+    }
+
+    fn reconnected(&self) -> bool {
+        let played_again = self.frames_at_loss.is_some_and(|frames| self.frames > frames + 10);
+        played_again
+            && self.screen.went_through(&[StreamState::Playing, StreamState::Reconnecting, StreamState::Playing])
+    }
+}
+
+impl Scenario for RtspReconnection {
+    fn step(&mut self, _sk: &mut Sk) -> bool {
+        let status = self.screen.observe();
+        self.frames = status.frames;
+        if self.frames_at_loss.is_none() && status.state == StreamState::Playing && status.frames >= 10 {
+            self.frames_at_loss = Some(status.frames);
+            self.server.close_clients();
+        }
+        self.reconnected()
+    }
+
+    fn check(&self) {
+        assert!(self.frames_at_loss.is_some(), "{} never played : {:?}", self.screen.id, self.screen.states);
+        assert!(
+            self.reconnected(),
+            "{} not reconnected : {:?}, {} frames",
+            self.screen.id,
+            self.screen.states,
+            self.frames
+        );
+    }
+}
+
+fn main() {
+    let mut settings = SkSettings::default();
+    settings
+        .app_name("headless_player")
+        .assets_folder("assets")
+        .log_filter(LogLevel::Warning)
+        .mode(AppMode::Offscreen)
+        .disable_unfocused_sleep(true);
+    let (mut sk, event_loop) = settings.init_with_event_loop().unwrap();
+    gstreamer_init().unwrap();
+
+    let mut scenarios: Vec<Box<dyn Scenario>> = vec![
+        Box::new(PlaybackToEos::new(&mut sk)),
+        Box::new(SeamlessLoop::new(&mut sk)),
+        Box::new(PlaylistAdvance::new(&mut sk)),
+        Box::new(RtspReconnection::new(&mut sk)),
+    ];
+    let mut over = vec![false; scenarios.len()];
+    let mut frame = 0;
+    SkClosures::run_app(
+        sk,
+        event_loop,
+        |sk, _token| {
+            frame += 1;
+            for (scenario, over) in scenarios.iter_mut().zip(over.iter_mut()) {
+                if !*over {
+                    *over = scenario.step(sk);
+                }
+            }
+            if over.iter().all(|over| *over) || frame >= MAX_FRAMES {
+                sk.quit(None);
+            }
+            thread::sleep(Duration::from_millis(10));
+        },
+        |_sk| {},
+    );
+    Sk::shutdown();
+
+    println!("{} frames stepped", frame);
+    for scenario in &scenarios {
+        scenario.check();
+    }
+}
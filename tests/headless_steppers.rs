@@ -0,0 +1,148 @@
+//! Launch StereoKit offscreen (a software renderer is enough, no GPU needed), play a Video1 and a Video2 fed by
+//! videotestsrc/audiotestsrc and check what reaches the textures and the sounds of the screens.
+//! The event loop must run on the main thread, so this test has no harness: `cargo test --test headless_steppers`
+
+use std::{path::PathBuf, thread, time::Duration};
+
+use gstreamer::{prelude::ElementExt, ClockTime, MessageType, MessageView, State};
+use stereokit_rust::{
+    event_loop::{SkClosures, StepperAction},
+    sk::{AppMode, Sk, SkSettings},
+    system::LogLevel,
+    tex::Tex,
+    util::Color32,
+};
+use stereokit_rust_gstreamer::{
+    probe::gstreamer_init,
+    status::{PlaybackStatusHandle, StreamState},
+    video1::{Video1, VideoType},
+    video2::Video2,
+};
+
+/// Frames stepped at most (10ms each) before giving up
+const MAX_FRAMES: u32 = 3000;
+/// Frames each screen must have received
+const MIN_VIDEO_FRAMES: u64 = 30;
+const WIDTH: i32 = 64;
+const HEIGHT: i32 = 36;
+
+/// What a screen showed while the frames were stepped
+#[derive(Debug, Default)]
+struct Observed {
+    /// The successive states, without repetition
+    states: Vec<StreamState>,
+    frames: u64,
+    audio_samples: u64,
+    /// The center pixel of the texture once a frame has been received
+    pixel: Option<Color32>,
+}
+
+impl Observed {
+    /// Read the status and the texture of the stepper `id`
+    fn observe(&mut self, id: &str, status: &PlaybackStatusHandle) {
+        let status = status.lock().unwrap().clone();
+        if self.states.last() != Some(&status.state) {
+            self.states.push(status.state);
+        }
+        if status.frames > self.frames {
+            if let Ok(tex) = Tex::find(format!("{}video", id)) {
+                let mut colors = vec![Color32::new(0, 0, 0, 0); (WIDTH * HEIGHT) as usize];
+                if tex.get_color_data::<Color32>(&mut colors, 0) {
+                    self.pixel = Some(colors[(HEIGHT / 2 * WIDTH + WIDTH / 2) as usize]);
+                }
+            }
+        }
+        self.frames = status.frames;
+        self.audio_samples = status.audio_samples;
+    }
+
+    fn done(&self) -> bool {
+        self.frames >= MIN_VIDEO_FRAMES && self.audio_samples > 0 && self.pixel.is_some()
+    }
+
+    /// The states went from Idle to Playing, video frames and audio samples were received and the texture shows
+    /// `expected` (with some tolerance for the color conversions and the compression)
+    fn check(&self, name: &str, expected: (u8, u8, u8)) {
+        assert_eq!(self.states.first(), Some(&StreamState::Idle), "{} : {:?}", name, self.states);
+        assert!(self.states.contains(&StreamState::Playing), "{} never played : {:?}", name, self.states);
+        assert!(!self.states.contains(&StreamState::Error), "{} : {:?}", name, self.states);
+        assert!(self.frames >= MIN_VIDEO_FRAMES, "{} : {} frames", name, self.frames);
+        assert!(self.audio_samples > 0, "{} : no audio sample", name);
+        let pixel = self.pixel.unwrap_or_else(|| panic!("{} : the texture has not been read", name));
+        let close = |a: u8, b: u8| a.abs_diff(b) < 60;
+        assert!(
+            close(pixel.r, expected.0) && close(pixel.g, expected.1) && close(pixel.b, expected.2),
+            "{} : pixel {:?} instead of {:?}",
+            name,
+            pixel,
+            expected
+        );
+    }
+}
+
+/// A WebM file of 5s with a blue video and a sine tone for Video2
+fn write_media() -> PathBuf {
+    let path = std::env::temp_dir().join(format!("stereokit_rust_gstreamer_headless_{}.webm", std::process::id()));
+    let pipeline = gstreamer::parse::launch(&format!(
+        "videotestsrc num-buffers=150 pattern=blue ! video/x-raw,width={WIDTH},height={HEIGHT},framerate=30/1 \
+         ! vp8enc ! webmmux name=mux ! filesink location={} \
+         audiotestsrc num-buffers=216 ! audioconvert ! vorbisenc ! mux.",
+        path.display()
+    ))
+    .unwrap();
+    pipeline.set_state(State::Playing).unwrap();
+    let bus = pipeline.bus().unwrap();
+    let msg = bus.timed_pop_filtered(ClockTime::from_seconds(30), &[MessageType::Eos, MessageType::Error]);
+    pipeline.set_state(State::Null).unwrap();
+    assert!(matches!(msg.as_ref().map(|msg| msg.view()), Some(MessageView::Eos(..))), "{:?}", msg);
+    path
+}
+
+fn main() {
+    let mut settings = SkSettings::default();
+    settings
+        .app_name("headless_steppers")
+        .assets_folder("assets")
+        .log_filter(LogLevel::Warning)
+        .mode(AppMode::Offscreen)
+        .disable_unfocused_sleep(true);
+    let (mut sk, event_loop) = settings.init_with_event_loop().unwrap();
+    gstreamer_init().unwrap();
+
+    let mut video1 = Video1::new(VideoType::TestPattern { pattern: "red".into(), tone: Some("sine".into()) });
+    video1.width = WIDTH;
+    video1.height = HEIGHT;
+    let status1 = video1.status_handle();
+    sk.push_action(StepperAction::add("Video1", video1));
+
+    let media = write_media();
+    let mut video2 = Video2::new(format!("file://{}", media.display()), false);
+    video2.width = WIDTH;
+    video2.height = HEIGHT;
+    let status2 = video2.status_handle();
+    sk.push_action(StepperAction::add("Video2", video2));
+
+    let mut observed1 = Observed::default();
+    let mut observed2 = Observed::default();
+    let mut frame = 0;
+    SkClosures::run_app(
+        sk,
+        event_loop,
+        |sk, _token| {
+            frame += 1;
+            observed1.observe("Video1", &status1);
+            observed2.observe("Video2", &status2);
+            if (observed1.done() && observed2.done()) || frame >= MAX_FRAMES {
+                sk.quit(None);
+            }
+            thread::sleep(Duration::from_millis(10));
+        },
+        |_sk| {},
+    );
+    Sk::shutdown();
+    let _ = std::fs::remove_file(media);
+
+    println!("{} frames stepped, Video1 {:?}, Video2 {:?}", frame, observed1, observed2);
+    observed1.check("Video1", (255, 0, 0));
+    observed2.check("Video2", (0, 0, 255));
+}
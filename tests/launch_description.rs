@@ -0,0 +1,45 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{receive_frames, Driver};
+use stereokit_rust_gstreamer::video1::{VideoType, VideoTypeSource};
+
+fn launch_video(description: &str) -> VideoTypeSource {
+    VideoTypeSource::new(VideoType::Launch { description: description.into() })
+}
+
+#[test]
+fn launch_placeholders_replaced() {
+    let description = "videotestsrc is-live=true ! video/x-raw,width=160,height=120 ! queue ! fakesink name=videosink \
+                       audiotestsrc is-live=true ! queue ! fakesink name=audiosink";
+    let (mut driver, status) = receive_frames(launch_video(description), 5);
+    assert!(status.frames >= 5, "No frame from the launch description : {:?}", status);
+    assert_eq!(status.video_size, Some((160, 120)));
+    let sound = driver.run_until(Duration::from_secs(5), |status| status.audio_samples > 0);
+    assert!(sound, "No sample from the launch description : {:?}", driver.status());
+}
+
+#[test]
+fn launch_placeholder_in_a_bin() {
+    let description = "( videotestsrc is-live=true ! video/x-raw,width=160,height=120 ! queue \
+                       ! fakesink name=videosink )";
+    let (_driver, status) = receive_frames(launch_video(description), 5);
+    assert!(status.frames >= 5, "No frame from the placeholder of the sub-bin : {:?}", status);
+}
+
+#[test]
+fn launch_without_placeholder() {
+    let Err(err) = Driver::start(launch_video("videotestsrc ! fakesink")) else {
+        panic!("A description without videosink nor audiosink has been launched");
+    };
+    assert!(err.to_string().contains("No element named videosink or audiosink"), "{}", err);
+}
+
+#[test]
+fn launch_unlinked_placeholder() {
+    let Err(err) = Driver::start(launch_video("videotestsrc ! fakesink fakesink name=videosink")) else {
+        panic!("An unlinked videosink has been replaced");
+    };
+    assert!(err.to_string().contains("videosink is not linked"), "{}", err);
+}
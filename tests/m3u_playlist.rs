@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use stereokit_rust_gstreamer::playlist::{parse_m3u, parse_m3u_file, to_path, to_uri, PlaylistEntry};
+
+const EXTENDED_M3U: &str = "\u{feff}#EXTM3U
+#EXTINF:52,Sintel trailer
+sintel_trailer-480p.webm
+
+#EXTINF:-1 tvg-id=\"live\",Live stream
+https://example.com/live/index.m3u8
+# a comment
+/data/videos/test.mp4
+";
+
+#[test]
+fn parse_extended_m3u() {
+    let entries = parse_m3u(EXTENDED_M3U, Some(Path::new("/videos")));
+    assert_eq!(
+        entries,
+        vec![
+            PlaylistEntry {
+                location: "/videos/sintel_trailer-480p.webm".into(),
+                title: Some("Sintel trailer".into()),
+                duration: Some(52.0),
+            },
+            PlaylistEntry {
+                location: "https://example.com/live/index.m3u8".into(),
+                title: Some("Live stream".into()),
+                duration: Some(-1.0),
+            },
+            PlaylistEntry { location: "/data/videos/test.mp4".into(), title: None, duration: None },
+        ]
+    );
+}
+
+#[test]
+fn parse_m3u_from_file() {
+    let dir = std::env::temp_dir().join("stereokit_rust_gstreamer_m3u");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("playlist.m3u");
+    // an old m3u may be latin-1
+    std::fs::write(&file, b"first.mkv\r\nsecond\xe9.webm\r\n").unwrap();
+
+    let entries = parse_m3u_file(&file).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(Path::new(&entries[0].location), dir.join("first.mkv"));
+    assert!(entries[1].location.ends_with(".webm"));
+}
+
+#[test]
+fn locations_to_uri_and_path() {
+    assert_eq!(to_uri("/videos/a.webm"), "file:/videos/a.webm");
+    assert_eq!(to_uri("https://example.com/a.webm"), "https://example.com/a.webm");
+    assert_eq!(to_path("file:///videos/a.webm"), "/videos/a.webm");
+    assert_eq!(to_path("file:/videos/a.webm"), "/videos/a.webm");
+    assert_eq!(to_path("/videos/a.webm"), "/videos/a.webm");
+}
@@ -0,0 +1,47 @@
+//! The ranks of the registry are shared by the whole process: this test has its own binary so the decoders of the
+//! other tests are not changed while they run.
+
+use gstreamer::{glib::translate::IntoGlib, prelude::PluginFeatureExtManual, Caps, PluginFeature, Rank, Registry};
+use stereokit_rust_gstreamer::{
+    decoder::DecoderResolver,
+    probe::{apply_rank_overrides, RankOverride},
+};
+
+/// Give back its rank to a feature of the registry when dropped, even if the test fails
+struct RankGuard {
+    feature: PluginFeature,
+    rank: Rank,
+}
+
+impl RankGuard {
+    fn save(factory: &str) -> Self {
+        let feature =
+            Registry::get().lookup_feature(factory).unwrap_or_else(|| panic!("No {} in the registry", factory));
+        Self { rank: feature.rank(), feature }
+    }
+}
+
+impl Drop for RankGuard {
+    fn drop(&mut self) {
+        self.feature.set_rank(self.rank);
+    }
+}
+
+#[test]
+fn rank_overrides_change_the_decoders() {
+    gstreamer::init().unwrap();
+    let _guard = RankGuard::save("vp9dec");
+    let vp9 = Caps::new_empty_simple("video/x-vp9");
+    let resolver = DecoderResolver { hardware_first: false, ..Default::default() };
+    let overrides = vec![
+        RankOverride { factory: "vp9dec".into(), rank: Rank::PRIMARY.into_glib() + 100 },
+        RankOverride { factory: "no-such-element".into(), rank: 0 },
+    ];
+    assert_eq!(apply_rank_overrides(&overrides), vec!["no-such-element".to_string()]);
+    assert_eq!(resolver.candidates(&vp9)[0].name, "vp9dec");
+    assert_eq!(resolver.candidates(&vp9)[0].rank, Rank::PRIMARY.into_glib() + 100);
+
+    // a decoder of rank NONE is no more a candidate
+    apply_rank_overrides(&[RankOverride { factory: "vp9dec".into(), rank: 0 }]);
+    assert!(resolver.candidates(&vp9).iter().all(|candidate| candidate.name != "vp9dec"));
+}
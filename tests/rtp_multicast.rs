@@ -0,0 +1,51 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{element_of, free_port, rtp_raw_video, start_rtp_raw, RtpRawSender};
+use gstreamer::prelude::ObjectExt;
+use stereokit_rust_gstreamer::rtp::UdpSettings;
+
+const SIZE: (i32, i32) = (64, 48);
+const GROUP: &str = "239.255.42.42";
+
+/// The number of frames received in 3s by a RtpRawStream with these settings from a sender to `host`
+fn received_frames(host: &str, udp: &UdpSettings) -> u64 {
+    let port = free_port();
+    let mut source = rtp_raw_video(port, "RGBA");
+    source.udp = udp.clone();
+    let mut driver = start_rtp_raw(source, SIZE).unwrap();
+    let _sender = RtpRawSender::start(host, port, "RGBA", SIZE, 0.0);
+    driver.run_until(Duration::from_secs(3), |status| status.frames > 0);
+    driver.status().frames
+}
+
+#[test]
+fn rtp_multicast_loopback() {
+    let udp = UdpSettings { address: Some(GROUP.into()), ..Default::default() };
+    assert!(udp.is_multicast());
+    assert!(received_frames(GROUP, &udp) > 0, "No frame received from the multicast group");
+}
+
+#[test]
+fn rtp_multicast_buffer_size() {
+    let mut source = rtp_raw_video(free_port(), "RGBA");
+    source.udp = UdpSettings { address: Some(GROUP.into()), buffer_size: Some(1 << 20), ..Default::default() };
+    let driver = start_rtp_raw(source, SIZE).unwrap();
+    let udpsrc = element_of(driver.pipeline().unwrap(), "udpsrc").expect("No udpsrc in the pipeline");
+    assert_eq!(udpsrc.property::<i32>("buffer-size"), 1 << 20);
+    assert_eq!(udpsrc.property::<String>("address"), GROUP);
+}
+
+#[test]
+fn rtp_source_filter() {
+    let allowed = UdpSettings { sources: vec!["127.0.0.1".into()], ..Default::default() };
+    assert!(received_frames("127.0.0.1", &allowed) > 0, "The packets of an allowed sender are dropped");
+
+    let filtered = UdpSettings { sources: vec!["192.0.2.1".into()], ..Default::default() };
+    assert_eq!(received_frames("127.0.0.1", &filtered), 0, "The packets of a filtered sender are received");
+
+    let mut invalid = rtp_raw_video(free_port(), "RGBA");
+    invalid.udp = UdpSettings { sources: vec!["nowhere".into()], ..Default::default() };
+    assert!(start_rtp_raw(invalid, SIZE).is_err());
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{free_port, rtp_raw_video, start_rtp_raw, RtpRawSender};
+use gstreamer::Caps;
+use stereokit_rust_gstreamer::video1::rtp_raw_caps;
+
+const SIZE: (i32, i32) = (320, 240);
+
+/// Frames of a local rtpvrawpay sender received by a RtpRawStream with this sampling
+fn loopback(sampling: &str, format: &str) {
+    let port = free_port();
+    let mut driver = start_rtp_raw(rtp_raw_video(port, sampling), SIZE).unwrap();
+    let _sender = RtpRawSender::start("127.0.0.1", port, format, SIZE, 0.0);
+
+    let received = driver.run_until(Duration::from_secs(5), |status| status.frames >= 10);
+    let status = driver.status();
+    assert!(received, "No frame received from the rtpvrawpay sender : {:?}", status);
+    assert_eq!(status.video_size, Some((SIZE.0 as u32, SIZE.1 as u32)));
+}
+
+#[test]
+fn rtp_raw_rgba_loopback() {
+    loopback("RGBA", "RGBA");
+}
+
+#[test]
+fn rtp_raw_yuv420_loopback() {
+    loopback("YCbCr-4:2:0", "I420");
+}
+
+#[test]
+fn rtp_raw_caps_parameters() {
+    gstreamer::init().unwrap();
+    let caps = rtp_raw_caps(SIZE.0, SIZE.1, "RGBA", 30);
+    let expected = "application/x-rtp, media=(string)video, encoding-name=(string)RAW, sampling=(string)RGBA, \
+                    width=(string)320, height=(string)240, depth=(string)8";
+    assert!(caps.can_intersect(&expected.parse::<Caps>().unwrap()), "{}", caps);
+}
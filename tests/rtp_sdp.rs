@@ -0,0 +1,142 @@
+use std::net::UdpSocket;
+
+use gstreamer::{
+    prelude::{Cast, ElementExt, GstBinExtManual},
+    ClockTime, Element, ElementFactory, Pipeline, State,
+};
+use gstreamer_app::AppSink;
+use gstreamer_video::{VideoCapsBuilder, VideoFormat, VideoInfo};
+use stereokit_rust_gstreamer::{
+    decoder::DecoderResolver,
+    rtp::{parse_sdp, rtp_decode_elements, RtpCodec, RtpSettings},
+};
+
+const H264_SDP: &str = "v=0
+o=- 0 0 IN IP4 127.0.0.1
+s=No Name
+c=IN IP4 127.0.0.1
+t=0 0
+m=audio 5002 RTP/AVP 97
+c=IN IP4 10.0.0.1
+a=rtpmap:97 opus/48000/2
+m=video 5000 RTP/AVP 96
+a=rtpmap:96 H264/90000
+a=fmtp:96 packetization-mode=1; sprop-parameter-sets=Z0LAHtkDxWhAAAADAEAAAAwDxYuS,aMuMsg==; profile-level-id=42C01E
+";
+
+#[test]
+fn sdp_h264() {
+    gstreamer::init().unwrap();
+    let sdp = parse_sdp(H264_SDP).unwrap();
+    assert_eq!(sdp.port, 5000);
+    // the c= of the audio media is not the one of the video
+    assert_eq!(sdp.address.as_deref(), Some("127.0.0.1"));
+    assert!(sdp.sources.is_empty());
+    assert_eq!(sdp.settings.codec, RtpCodec::H264);
+    assert_eq!(sdp.settings.payload, 96);
+    assert_eq!(sdp.settings.clock_rate, 90000);
+    assert_eq!(sdp.settings.parameter("packetization-mode"), Some("1"));
+    assert_eq!(sdp.settings.parameter("sprop-parameter-sets"), Some("Z0LAHtkDxWhAAAADAEAAAAwDxYuS,aMuMsg=="));
+    assert_eq!(sdp.settings.parameter("profile-level-id"), Some("42C01E"));
+}
+
+#[test]
+fn sdp_raw() {
+    gstreamer::init().unwrap();
+    let sdp = parse_sdp(
+        "v=0
+m=video 5004/2 RTP/AVP 112
+c=IN IP4 239.1.1.1/32
+a=source-filter: incl IN IP4 239.1.1.1 192.168.1.10 192.168.1.11
+a=rtpmap:112 raw/90000
+a=fmtp:112 sampling=YCbCr-4:2:2; width=1280; height=720; depth=8; colorimetry=BT709
+a=framerate:25
+",
+    )
+    .unwrap();
+    assert_eq!(sdp.port, 5004);
+    assert_eq!(sdp.address.as_deref(), Some("239.1.1.1"));
+    assert_eq!(sdp.sources, vec!["192.168.1.10".to_string(), "192.168.1.11".to_string()]);
+    assert_eq!(sdp.settings.codec, RtpCodec::Raw);
+    assert_eq!(sdp.settings.payload, 112);
+    assert_eq!(sdp.settings.parameter("sampling"), Some("YCbCr-4:2:2"));
+    assert_eq!(sdp.settings.parameter("width"), Some("1280"));
+    assert_eq!(sdp.settings.parameter("a-framerate"), Some("25"));
+}
+
+#[test]
+fn sdp_errors() {
+    gstreamer::init().unwrap();
+    assert!(parse_sdp("v=0\nm=audio 5002 RTP/AVP 97\na=rtpmap:97 opus/48000/2\n").is_err());
+    assert!(parse_sdp("v=0\nm=video 5000 RTP/AVP 96\n").is_err());
+    assert!(parse_sdp("v=0\nm=video 5000 RTP/AVP 96\na=rtpmap:96 MP4V-ES/90000\n").is_err());
+}
+
+/// The first format with a supported codec is chosen, its fmtp lines are all read
+#[test]
+fn sdp_formats() {
+    gstreamer::init().unwrap();
+    let sdp = parse_sdp(
+        "v=0
+c=IN IP4 239.2.2.2/16
+m=audio 5002 RTP/AVP 97
+m=video 5000 RTP/AVP 26 98 96
+a=rtpmap:98 MP4V-ES/90000
+a=rtpmap:96 H265/90000
+a=fmtp:98 profile-level-id=1
+a=fmtp:96 sprop-vps=QAEMAf//
+a=fmtp:96 sprop-sps=QgEBAWA=; sprop-pps=RAHA8vA8kA==
+",
+    )
+    .unwrap();
+    // the connection of the session applies to the video media
+    assert_eq!(sdp.address.as_deref(), Some("239.2.2.2"));
+    assert_eq!(sdp.settings.codec, RtpCodec::H265);
+    assert_eq!(sdp.settings.payload, 96);
+    assert_eq!(sdp.settings.parameter("sprop-vps"), Some("QAEMAf//"));
+    assert_eq!(sdp.settings.parameter("sprop-pps"), Some("RAHA8vA8kA=="));
+    assert_eq!(sdp.settings.parameter("profile-level-id"), None);
+}
+
+/// A VP8 stream received with the caps and the decoding chain of a SDP
+#[test]
+fn rtp_vp8_loopback() {
+    gstreamer::init().unwrap();
+    let port = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let sdp = parse_sdp(&format!("v=0\nm=video {port} RTP/AVP 97\na=rtpmap:97 VP8/90000\n")).unwrap();
+    assert_eq!(sdp.settings, RtpSettings { codec: RtpCodec::VP8, payload: 97, ..Default::default() });
+
+    let receiver = Pipeline::default();
+    let udpsrc = ElementFactory::make("udpsrc")
+        .property("address", "127.0.0.1")
+        .property("port", sdp.port)
+        .property("caps", sdp.settings.caps())
+        .build()
+        .unwrap();
+    let convert = ElementFactory::make("videoconvert").build().unwrap();
+    let appsink = AppSink::builder().caps(&VideoCapsBuilder::new().format(VideoFormat::Rgba).build()).build();
+    let mut elements = vec![udpsrc];
+    elements.extend(rtp_decode_elements(&sdp.settings, &DecoderResolver::default()).unwrap().0);
+    elements.push(convert);
+    elements.push(appsink.clone().upcast());
+    receiver.add_many(&elements).unwrap();
+    Element::link_many(&elements).unwrap();
+    receiver.set_state(State::Playing).unwrap();
+
+    let sender = gstreamer::parse::launch(&format!(
+        "videotestsrc is-live=true ! video/x-raw,width=320,height=240,framerate=30/1 ! vp8enc deadline=1 \
+         ! rtpvp8pay pt=97 ! udpsink host=127.0.0.1 port={port}"
+    ))
+    .unwrap();
+    sender.set_state(State::Playing).unwrap();
+
+    let sample = appsink.try_pull_sample(ClockTime::from_seconds(5));
+
+    sender.set_state(State::Null).unwrap();
+    receiver.set_state(State::Null).unwrap();
+
+    let sample = sample.expect("No frame received from the rtpvp8pay sender");
+    let info = VideoInfo::from_caps(sample.caps().unwrap()).unwrap();
+    assert_eq!(info.width(), 320);
+    assert_eq!(info.height(), 240);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{free_port, rtp_raw_video, start_rtp_raw, RtpRawSender};
+use stereokit_rust_gstreamer::status::{PlaybackStatus, StreamState};
+
+const SIZE: (i32, i32) = (64, 48);
+
+#[test]
+fn rtp_loss_stats() {
+    for (drop_probability, lossy) in [(0.0, false), (0.2, true)] {
+        let port = free_port();
+        let mut source = rtp_raw_video(port, "RGBA");
+        source.rtp.latency = 50;
+        let mut driver = start_rtp_raw(source, SIZE).unwrap();
+        let _sender = RtpRawSender::start("127.0.0.1", port, "RGBA", SIZE, drop_probability);
+        driver.run_for(Duration::from_secs(2));
+
+        // update_status() reads the statistics of the jitterbuffer
+        let stats = driver.status().rtp_stats.expect("No jitterbuffer in the receiver");
+        assert!(stats.pushed > 0, "No packet received");
+        assert_eq!(stats.lost > 0, lossy, "{:?}", stats);
+        assert_eq!(stats.loss_percent() > 0.0, lossy);
+    }
+}
+
+#[test]
+fn rtp_sender_restart() {
+    let port = free_port();
+    let mut source = rtp_raw_video(port, "RGBA");
+    source.signal_timeout = 1.0;
+    let mut driver = start_rtp_raw(source, SIZE).unwrap();
+
+    let first_sender = RtpRawSender::start("127.0.0.1", port, "RGBA", SIZE, 0.0);
+    let playing = |status: &PlaybackStatus| status.state == StreamState::Playing && status.frames > 0;
+    assert!(driver.run_until(Duration::from_secs(5), playing), "No frame from the first sender");
+
+    // the signal is lost, the pipeline keeps playing
+    drop(first_sender);
+    let lost = driver.run_until(Duration::from_secs(5), |status| status.state == StreamState::SignalLost);
+    assert!(lost, "The signal is not lost : {:?}", driver.states);
+    assert!(driver.pipeline().is_some());
+
+    // and comes back with a new sender (new ssrc, sequence numbers and timestamps)
+    let frames = driver.status().frames;
+    let _second_sender = RtpRawSender::start("127.0.0.1", port, "RGBA", SIZE, 0.0);
+    let back = driver
+        .run_until(Duration::from_secs(5), |status| status.state == StreamState::Playing && status.frames > frames);
+    assert!(back, "No frame after the restart of the sender : {:?}", driver.states);
+    let expected = [StreamState::Playing, StreamState::SignalLost, StreamState::Playing];
+    assert!(driver.went_through(&expected), "{:?}", driver.states);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{Driver, RtspTestServer, HEIGHT, WIDTH};
+use stereokit_rust_gstreamer::{
+    player::SinkContext,
+    rtsp::RtspTransport,
+    status::{PlaybackStatus, StreamState},
+    video1::{VideoType, VideoTypeSource},
+};
+
+const USER: &str = "user";
+const PASSWORD: &str = "password";
+
+/// The Rtsp source of a Video1 (rtspsrc, a decodebin per stream and the sinks of the screen)
+fn rtsp_video(url: &str, transport: RtspTransport, credentials: Option<(&str, &str)>) -> VideoTypeSource {
+    let mut source = VideoTypeSource::new(VideoType::Rtsp { url: url.into(), latency: 200, transport });
+    source.user_id = credentials.map(|(user, _)| user.into());
+    source.user_pw = credentials.map(|(_, password)| password.into());
+    source
+}
+
+/// Play the Rtsp source until frames are received, the frames keep the size of the camera
+fn receive_frames(transport: RtspTransport, with_auth: bool, credentials: Option<(&str, &str)>) -> PlaybackStatus {
+    let server = RtspTestServer::start(with_auth.then_some((USER, PASSWORD)));
+    let source = rtsp_video(&server.url, transport, credentials);
+    let mut driver = Driver::start_with(source, SinkContext::offscreen(0, 0, true)).unwrap();
+    driver.run_until(Duration::from_secs(10), |status| status.frames > 0 || status.error.is_some());
+    driver.status()
+}
+
+#[test]
+fn rtsp_tcp_loopback() {
+    let status = receive_frames(RtspTransport::Tcp, false, None);
+    assert!(status.frames > 0, "No frame received over RTSP/TCP : {:?}", status);
+    assert_eq!(status.video_size, Some((WIDTH as u32, HEIGHT as u32)));
+}
+
+#[test]
+fn rtsp_udp_loopback() {
+    let status = receive_frames(RtspTransport::Udp, false, None);
+    assert!(status.frames > 0, "No frame received over RTSP/UDP : {:?}", status);
+    assert_eq!(status.video_size, Some((WIDTH as u32, HEIGHT as u32)));
+}
+
+#[test]
+fn rtsp_authentication() {
+    let status = receive_frames(RtspTransport::Tcp, true, Some((USER, PASSWORD)));
+    assert!(status.frames > 0, "Authentication refused : {:?}", status);
+}
+
+#[test]
+fn rtsp_wrong_password() {
+    let status = receive_frames(RtspTransport::Tcp, true, Some((USER, "wrong")));
+    // the camera may come back with the right password : the error is reported and the source reconnects
+    assert!(status.error.is_some(), "A wrong password must be an error : {:?}", status);
+    assert_eq!(status.state, StreamState::Reconnecting);
+    assert_eq!(status.frames, 0);
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use std::{
+    path::Path,
+    process::{Child, Command},
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::receive_frames;
+use stereokit_rust_gstreamer::{
+    capture::{capture_source, CaptureTarget},
+    video1::{VideoType, VideoTypeSource},
+};
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 480;
+
+/// A Xvfb display of its own for each test, killed when dropped
+struct Xvfb {
+    display: String,
+    process: Child,
+}
+
+impl Xvfb {
+    fn start(number: u32) -> Self {
+        let number = 100 + (std::process::id() % 400) * 4 + number;
+        let display = format!(":{number}");
+        let process = Command::new("Xvfb")
+            .args([display.as_str(), "-screen", "0", &format!("{WIDTH}x{HEIGHT}x24"), "-nolisten", "tcp"])
+            .spawn()
+            .expect("Xvfb is needed by the screen capture tests");
+        let socket = format!("/tmp/.X11-unix/X{number}");
+        let start = Instant::now();
+        while !Path::new(&socket).exists() && start.elapsed() < Duration::from_secs(10) {
+            thread::sleep(Duration::from_millis(50));
+        }
+        Self { display, process }
+    }
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// The ScreenCapture source of a Video1 (the capture bin, videorate and the sinks of the screen)
+fn capture_video(target: CaptureTarget, max_fps: i32) -> VideoTypeSource {
+    VideoTypeSource::new(VideoType::ScreenCapture { target, cursor: true, max_fps })
+}
+
+/// The size of the captured frames
+fn frame_size(target: CaptureTarget) -> Option<(u32, u32)> {
+    let (_driver, status) = receive_frames(capture_video(target, 0), 1);
+    assert!(status.frames > 0, "No frame captured : {:?}", status);
+    status.video_size
+}
+
+#[test]
+fn capture_display() {
+    let xvfb = Xvfb::start(0);
+    assert_eq!(frame_size(CaptureTarget::Display(Some(xvfb.display.clone()))), Some((WIDTH, HEIGHT)));
+}
+
+#[test]
+fn capture_region() {
+    let xvfb = Xvfb::start(1);
+    let target = CaptureTarget::Region { display: Some(xvfb.display.clone()), x: 10, y: 20, width: 100, height: 50 };
+    assert_eq!(frame_size(target), Some((100, 50)));
+
+    gstreamer::init().unwrap();
+    let empty = CaptureTarget::Region { display: None, x: 0, y: 0, width: 0, height: 50 };
+    assert!(capture_source(&empty, false).is_err());
+}
+
+#[test]
+fn capture_max_fps() {
+    let xvfb = Xvfb::start(2);
+    let (mut driver, status) = receive_frames(capture_video(CaptureTarget::Display(Some(xvfb.display.clone())), 5), 1);
+    assert!(status.frames > 0, "No frame captured : {:?}", status);
+
+    driver.run_for(Duration::from_secs(2));
+    let frames = driver.status().frames - status.frames;
+    assert!((2..=12).contains(&frames), "{} frames in 2s at 5fps max", frames);
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{free_port, receive_frames, Driver, HEIGHT, WIDTH};
+use gstreamer::{prelude::ElementExt, Element, State};
+use stereokit_rust_gstreamer::{
+    player::SinkContext,
+    srt::SrtMode,
+    status::PlaybackStatus,
+    video1::{VideoType, VideoTypeSource},
+};
+
+const PASSPHRASE: &str = "0123456789abcdef";
+
+/// A local srtsink sending a H264 test pattern in MPEG-TS, the stand-in of a contribution encoder. It stops when
+/// dropped.
+struct Sender(Element);
+
+impl Sender {
+    fn start(uri: &str, passphrase: Option<&str>) -> Self {
+        gstreamer::init().unwrap();
+        let passphrase =
+            passphrase.map(|passphrase| format!("passphrase={passphrase} pbkeylen=16")).unwrap_or_default();
+        let sender = gstreamer::parse::launch(&format!(
+            "videotestsrc is-live=true ! video/x-raw,width={WIDTH},height={HEIGHT},framerate=30/1 \
+             ! x264enc tune=zerolatency key-int-max=30 ! mpegtsmux ! srtsink uri={uri} latency=50 {passphrase}"
+        ))
+        .unwrap();
+        sender.set_state(State::Playing).unwrap();
+        Self(sender)
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        let _ = self.0.set_state(State::Null);
+    }
+}
+
+/// The Srt source of a Video1 (srtsrc, decodebin and the sinks of the screen)
+fn srt_video(uri: &str, passphrase: Option<&str>, mode: SrtMode) -> VideoTypeSource {
+    let passphrase = passphrase.map(|passphrase| passphrase.to_string());
+    let mut source = VideoTypeSource::new(VideoType::Srt { uri: uri.into(), latency: 50, passphrase });
+    source.srt_mode = mode;
+    source
+}
+
+/// The frames have the size of the sender and update_status() gave the statistics of the connection
+fn check_received(status: &PlaybackStatus) {
+    assert!(status.frames > 0, "No frame received : {:?}", status);
+    assert_eq!(status.video_size, Some((WIDTH as u32, HEIGHT as u32)));
+    let stats = status.srt_stats.as_ref().expect("No SRT statistics");
+    assert!(stats.packets_received > 0, "{:?}", stats);
+    assert!(stats.rtt >= 0.0);
+}
+
+#[test]
+fn srt_caller_loopback() {
+    let port = free_port();
+    let _sender = Sender::start(&format!("srt://:{port}"), None);
+    let (_driver, status) = receive_frames(srt_video(&format!("srt://127.0.0.1:{port}"), None, SrtMode::Caller), 10);
+    check_received(&status);
+}
+
+#[test]
+fn srt_listener_loopback() {
+    let port = free_port();
+    let source = srt_video(&format!("srt://:{port}"), None, SrtMode::Listener);
+    let mut driver = Driver::start_with(source, SinkContext::offscreen(0, 0, true)).expect("Unable to listen");
+    let _sender = Sender::start(&format!("srt://127.0.0.1:{port}?mode=caller"), None);
+    driver.run_until(Duration::from_secs(10), |status| status.frames >= 10);
+    check_received(&driver.status());
+}
+
+#[test]
+fn srt_passphrase() {
+    let port = free_port();
+    let _sender = Sender::start(&format!("srt://:{port}"), Some(PASSPHRASE));
+    let source = srt_video(&format!("srt://127.0.0.1:{port}"), Some(PASSPHRASE), SrtMode::Caller);
+    let (_driver, status) = receive_frames(source, 10);
+    check_received(&status);
+}
+
+#[test]
+fn srt_wrong_passphrase() {
+    let port = free_port();
+    let _sender = Sender::start(&format!("srt://:{port}"), Some(PASSPHRASE));
+    let source = srt_video(&format!("srt://127.0.0.1:{port}"), Some("wrong passphrase"), SrtMode::Caller);
+    let mut driver = Driver::start(source).unwrap();
+    driver.run_for(Duration::from_secs(5));
+    assert_eq!(driver.status().frames, 0, "A frame has been decrypted with a wrong passphrase");
+}
@@ -0,0 +1,87 @@
+use gstreamer::ClockTime;
+use stereokit_rust_gstreamer::subtitles::{parse_subtitles, SubtitleCue, Subtitles};
+
+const SRT: &str = "1
+00:00:01,000 --> 00:00:04,500
+Hello <i>world</i>
+
+2
+00:00:03,000 --> 00:00:06,000
+Two lines
+&lt;second&gt;
+";
+
+const WEBVTT: &str = "WEBVTT - with a title
+
+NOTE a comment
+on two lines
+
+intro
+00:01.250 --> 00:02.000 align:start position:10%
+<v Bob>Hi</v>
+
+01:00:00.000 --> 01:00:01.000
+Later
+";
+
+fn cue(start_ms: u64, end_ms: u64, text: &str) -> SubtitleCue {
+    SubtitleCue { start: ClockTime::from_mseconds(start_ms), end: ClockTime::from_mseconds(end_ms), text: text.into() }
+}
+
+#[test]
+fn parse_srt() {
+    let cues = parse_subtitles(&SRT.replace('\n', "\r\n"));
+    assert_eq!(cues, vec![cue(1000, 4500, "Hello world"), cue(3000, 6000, "Two lines\n<second>")]);
+}
+
+#[test]
+fn parse_webvtt() {
+    let cues = parse_subtitles(WEBVTT);
+    assert_eq!(cues, vec![cue(1250, 2000, "Hi"), cue(3_600_000, 3_601_000, "Later")]);
+}
+
+#[test]
+fn text_at_position() {
+    let mut subtitles = Subtitles::default();
+    subtitles.add_cue(cue(3000, 6000, "second"));
+    subtitles.add_cue(cue(1000, 4500, "first"));
+    // sent again after a seek
+    subtitles.add_cue(cue(1000, 4500, "first"));
+
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(500)), None);
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(2000)), Some("first".into()));
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(4000)), Some("first\nsecond".into()));
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(6000)), None);
+
+    // the sidecar file replaces the embedded track
+    subtitles.set_sidecar(Some(parse_subtitles(WEBVTT)));
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(1500)), Some("Hi".into()));
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(4000)), None);
+    subtitles.set_sidecar(None);
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(4000)), Some("first\nsecond".into()));
+
+    subtitles.visible = false;
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(4000)), None);
+}
+
+#[test]
+fn ended_cues_pruned() {
+    let mut subtitles = Subtitles::default();
+    subtitles.add_cue(cue(1000, 4500, "first"));
+    subtitles.add_cue(cue(3000, 6000, "second"));
+    subtitles.add_cue(cue(7000, 8000, "third"));
+
+    subtitles.prune_embedded(ClockTime::from_mseconds(5000));
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(2000)), None);
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(5000)), Some("second".into()));
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(7500)), Some("third".into()));
+
+    // sent again after a seek backward
+    subtitles.add_cue(cue(1000, 4500, "first"));
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(2000)), Some("first".into()));
+
+    // the cues of a sidecar file are kept
+    subtitles.set_sidecar(Some(parse_subtitles(WEBVTT)));
+    subtitles.prune_embedded(ClockTime::from_seconds(3601));
+    assert_eq!(subtitles.text_at(ClockTime::from_mseconds(1500)), Some("Hi".into()));
+}
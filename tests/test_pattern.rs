@@ -0,0 +1,55 @@
+use gstreamer::{
+    prelude::{Cast, ElementExt, GstBinExtManual},
+    ClockTime, Element, ElementFactory, Fraction, Pipeline, State,
+};
+use gstreamer_app::AppSink;
+use gstreamer_audio::AudioInfo;
+use gstreamer_video::{VideoCapsBuilder, VideoFormat, VideoInfo};
+use stereokit_rust_gstreamer::pattern::{test_pattern_bin, test_tone_source};
+
+#[test]
+fn pattern_size_and_framerate() {
+    gstreamer::init().unwrap();
+    let pipeline = Pipeline::default();
+    let video = test_pattern_bin("ball", 320, 180, 15).unwrap();
+    let convert = ElementFactory::make("videoconvert").build().unwrap();
+    let appsink = AppSink::builder().caps(&VideoCapsBuilder::new().format(VideoFormat::Rgba).build()).build();
+    pipeline.add_many([video.upcast_ref(), &convert, appsink.upcast_ref()]).unwrap();
+    Element::link_many([video.upcast_ref(), &convert, appsink.upcast_ref()]).unwrap();
+    pipeline.set_state(State::Playing).unwrap();
+
+    let sample = appsink.try_pull_sample(ClockTime::from_seconds(5));
+    pipeline.set_state(State::Null).unwrap();
+
+    let info = VideoInfo::from_caps(sample.expect("No frame of the test pattern").caps().unwrap()).unwrap();
+    assert_eq!((info.width(), info.height()), (320, 180));
+    assert_eq!(info.fps(), Fraction::new(15, 1));
+}
+
+#[test]
+fn tone_samples() {
+    gstreamer::init().unwrap();
+    let pipeline = Pipeline::default();
+    let audio = test_tone_source("ticks").unwrap();
+    let appsink = AppSink::builder().build();
+    pipeline.add_many([&audio, appsink.upcast_ref()]).unwrap();
+    audio.link(&appsink).unwrap();
+    pipeline.set_state(State::Playing).unwrap();
+
+    let sample = appsink.try_pull_sample(ClockTime::from_seconds(5));
+    pipeline.set_state(State::Null).unwrap();
+
+    let sample = sample.expect("No sample of the test tone");
+    let info = AudioInfo::from_caps(sample.caps().unwrap()).unwrap();
+    let frames = sample.buffer().unwrap().size() / info.bpf() as usize;
+    assert!(frames > 0);
+}
+
+#[test]
+fn unknown_pattern_and_tone() {
+    gstreamer::init().unwrap();
+    let err = test_pattern_bin("no-such-pattern", 320, 180, 15).unwrap_err();
+    assert!(err.to_string().contains("smpte"), "{}", err);
+    let err = test_tone_source("no-such-wave").unwrap_err();
+    assert!(err.to_string().contains("sine"), "{}", err);
+}
@@ -0,0 +1,60 @@
+use stereokit_rust_gstreamer::tracks::{selection_with, set_selected, TrackInfo, TrackKind};
+
+fn track(stream_id: &str, kind: TrackKind, index: usize, selected: bool) -> TrackInfo {
+    TrackInfo {
+        stream_id: stream_id.into(),
+        kind,
+        index,
+        language: None,
+        title: None,
+        codec: None,
+        bitrate: None,
+        selected,
+    }
+}
+
+/// A video, two audio tracks (the first one selected) and a subtitle track
+fn collection() -> Vec<TrackInfo> {
+    vec![
+        track("video0", TrackKind::Video, 0, true),
+        track("audio0", TrackKind::Audio, 0, true),
+        track("audio1", TrackKind::Audio, 1, false),
+        track("text0", TrackKind::Text, 0, true),
+    ]
+}
+
+#[test]
+fn selection_replaces_the_track_of_its_kind() {
+    let tracks = collection();
+    let selection = selection_with(&tracks, TrackKind::Audio, 1).unwrap();
+    assert_eq!(selection, ["video0", "text0", "audio1"]);
+
+    // the selected track again keeps the selection
+    let selection = selection_with(&tracks, TrackKind::Audio, 0).unwrap();
+    assert_eq!(selection, ["video0", "text0", "audio0"]);
+}
+
+#[test]
+fn selection_of_an_unselected_kind() {
+    let mut tracks = collection();
+    tracks[3].selected = false;
+    let selection = selection_with(&tracks, TrackKind::Text, 0).unwrap();
+    assert_eq!(selection, ["video0", "audio0", "text0"]);
+}
+
+#[test]
+fn selection_of_a_missing_track() {
+    let tracks = collection();
+    let err = selection_with(&tracks, TrackKind::Audio, 2).unwrap_err();
+    assert_eq!(err.to_string(), "No audio track 2");
+    assert!(selection_with(&tracks, TrackKind::Video, 1).is_err());
+}
+
+#[test]
+fn selected_tracks_follow_streams_selected() {
+    let mut tracks = collection();
+    set_selected(&mut tracks, &["video0".into(), "audio1".into()]);
+    let selected: Vec<&str> =
+        tracks.iter().filter(|track| track.selected).map(|track| track.stream_id.as_str()).collect();
+    assert_eq!(selected, ["video0", "audio1"]);
+}
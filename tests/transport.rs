@@ -0,0 +1,89 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{encoded_file, Driver};
+use gstreamer::ClockTime;
+use stereokit_rust::event_loop::StepperAction;
+use stereokit_rust_gstreamer::{
+    status::StreamState,
+    tracks::TrackKind,
+    transport::{apply, PlayerCommand},
+    video1::{VideoType, VideoTypeSource},
+};
+
+/// 5s of H264 in mp4 with a keyframe every 10 frames, qtdemux plays it backward
+const H264_MP4: &str = "videotestsrc num-buffers=150 ! video/x-raw,width=160,height=120,framerate=30/1 \
+    ! x264enc key-int-max=10 ! mp4mux";
+
+#[test]
+fn commands_round_trip() {
+    let commands = [
+        PlayerCommand::Play,
+        PlayerCommand::Pause,
+        PlayerCommand::Seek(12.5),
+        PlayerCommand::SeekBy(-5.0),
+        PlayerCommand::FrameStep,
+        PlayerCommand::Rate(-0.5),
+        PlayerCommand::Volume(2.0),
+        PlayerCommand::Mute(true),
+        PlayerCommand::Equalizer(2, -6.5),
+        PlayerCommand::SelectTrack(TrackKind::Audio, 1),
+        PlayerCommand::Subtitles(false),
+        PlayerCommand::Variant(Some(3)),
+        PlayerCommand::Variant(None),
+    ];
+    for command in commands {
+        let StepperAction::Event(id, key, value) = command.to_event("Video1") else {
+            panic!("{:?} is not an event", command);
+        };
+        assert_eq!(id, "Video1");
+        assert_eq!(PlayerCommand::from_event(&key, &value), Some(command), "{} : {}", key, value);
+    }
+    assert_eq!(PlayerCommand::from_event("PlayerSeek", "soon"), None);
+    assert_eq!(PlayerCommand::from_event("PlayerSelectTrack", "music:1"), None);
+    assert_eq!(PlayerCommand::from_event("NotAPlayerCommand", ""), None);
+}
+
+#[test]
+fn seek_and_rate() {
+    let uri = encoded_file("transport.mp4", H264_MP4).to_string_lossy().to_string();
+    let mut driver = Driver::start(VideoTypeSource::new(VideoType::H264File { uri })).unwrap();
+    let playing = driver.run_until(Duration::from_secs(5), |status| status.state == StreamState::Playing);
+    assert!(playing, "{:?}", driver.status());
+    let pipeline = driver.pipeline().unwrap().clone();
+    let position = |driver: &Driver<VideoTypeSource>| driver.status().position.unwrap_or(ClockTime::ZERO);
+    let mut rate = 1.0;
+
+    // seek forward
+    apply(&pipeline, &mut rate, PlayerCommand::Seek(2.0)).unwrap();
+    driver.run_for(Duration::from_millis(200));
+    let after_seek = position(&driver);
+    assert!((2000..2500).contains(&after_seek.mseconds()), "position {} after the seek to 2s", after_seek);
+
+    // twice faster
+    apply(&pipeline, &mut rate, PlayerCommand::Rate(2.0)).unwrap();
+    assert_eq!(rate, 2.0);
+    let start = position(&driver);
+    driver.run_for(Duration::from_millis(500));
+    let forward = position(&driver).mseconds() as i64 - start.mseconds() as i64;
+    assert!((600..=1400).contains(&forward), "{}ms played in 500ms at rate 2", forward);
+
+    // backward
+    apply(&pipeline, &mut rate, PlayerCommand::Rate(-1.0)).unwrap();
+    assert_eq!(rate, -1.0);
+    driver.run_for(Duration::from_millis(100));
+    let start = position(&driver);
+    driver.run_for(Duration::from_millis(500));
+    let backward = start.mseconds() as i64 - position(&driver).mseconds() as i64;
+    assert!(backward > 200, "{}ms played backward in 500ms at rate -1", backward);
+    assert!(apply(&pipeline, &mut rate, PlayerCommand::Rate(0.0)).is_err());
+    assert_eq!(rate, -1.0);
+
+    // forward again to the end of the media: the stop of the segment is the end
+    apply(&pipeline, &mut rate, PlayerCommand::Rate(1.0)).unwrap();
+    apply(&pipeline, &mut rate, PlayerCommand::Seek(4.5)).unwrap();
+    let eos = driver.run_until(Duration::from_secs(5), |status| status.state == StreamState::Eos);
+    assert!(eos, "{:?}", driver.status());
+    assert!(driver.status().error.is_none());
+}
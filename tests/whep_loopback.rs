@@ -0,0 +1,156 @@
+mod common;
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::{receive_frames, HEIGHT, WIDTH};
+use gstreamer::{
+    prelude::{Cast, ElementExt, GstBinExt, ObjectExt},
+    Bin, Element, Promise, State, Structure,
+};
+use gstreamer_sdp::SDPMessage;
+use gstreamer_webrtc::{WebRTCICEGatheringState, WebRTCSDPType, WebRTCSessionDescription};
+use stereokit_rust_gstreamer::{
+    status::StreamState,
+    video1::{VideoType, VideoTypeSource},
+};
+
+const TOKEN: &str = "secret";
+
+/// A VP8 test pattern sent by a webrtcbin answering `offer`, the stand-in of the media server behind the endpoint
+fn loopback_sender(offer: &str) -> (Element, String) {
+    let sender = gstreamer::parse::launch(&format!(
+        "videotestsrc is-live=true ! video/x-raw,width={WIDTH},height={HEIGHT},framerate=30/1 \
+         ! vp8enc deadline=1 ! rtpvp8pay pt=96 \
+         ! application/x-rtp,media=video,encoding-name=VP8,payload=96,clock-rate=90000 \
+         ! webrtcbin name=sender bundle-policy=max-bundle"
+    ))
+    .unwrap();
+    let webrtcbin = sender.downcast_ref::<Bin>().unwrap().by_name("sender").unwrap();
+    sender.set_state(State::Playing).unwrap();
+
+    let offer = SDPMessage::parse_buffer(offer.as_bytes()).unwrap();
+    let promise = Promise::new();
+    webrtcbin.emit_by_name::<()>(
+        "set-remote-description",
+        &[&WebRTCSessionDescription::new(WebRTCSDPType::Offer, offer), &promise],
+    );
+    promise.wait();
+
+    let promise = Promise::new();
+    webrtcbin.emit_by_name::<()>("create-answer", &[&None::<Structure>, &promise]);
+    promise.wait();
+    let answer = promise.get_reply().unwrap().get::<WebRTCSessionDescription>("answer").unwrap();
+    webrtcbin.emit_by_name::<()>("set-local-description", &[&answer, &None::<Promise>]);
+
+    // the answer is sent with all the ICE candidates
+    let start = Instant::now();
+    while webrtcbin.property::<WebRTCICEGatheringState>("ice-gathering-state") != WebRTCICEGatheringState::Complete
+        && start.elapsed() < Duration::from_secs(10)
+    {
+        thread::sleep(Duration::from_millis(50));
+    }
+    let answer = webrtcbin.property::<WebRTCSessionDescription>("local-description");
+    (sender, answer.sdp().as_text().unwrap())
+}
+
+/// A minimal WHEP endpoint on /whep: an offer with the bearer token gets the answer of a new loopback sender and the
+/// url of its resource. The paths of the DELETE requests are recorded.
+fn serve() -> (String, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let deleted = Arc::new(Mutex::new(vec![]));
+    let deleted_paths = deleted.clone();
+    thread::spawn(move || {
+        let mut senders = vec![];
+        for mut stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line).is_err() {
+                continue;
+            }
+            let mut content_length = 0;
+            let mut authorized = false;
+            let mut header = String::new();
+            while reader.read_line(&mut header).is_ok_and(|size| size > 2) {
+                let (name, value) = header.split_once(':').unwrap_or_default();
+                match name.to_ascii_lowercase().as_str() {
+                    "content-length" => content_length = value.trim().parse().unwrap_or(0),
+                    "authorization" => authorized = value.trim() == format!("Bearer {TOKEN}"),
+                    _ => {}
+                }
+                header.clear();
+            }
+            let mut body = vec![0; content_length];
+            if reader.read_exact(&mut body).is_err() {
+                continue;
+            }
+
+            let mut request = request_line.split_whitespace();
+            let response = match (request.next(), request.next()) {
+                (Some("POST"), Some("/whep")) if authorized => {
+                    let (sender, answer) = loopback_sender(&String::from_utf8_lossy(&body));
+                    senders.push(sender);
+                    format!(
+                        "HTTP/1.1 201 Created\r\nContent-Type: application/sdp\r\nLocation: /whep/resource/{}\r\n\
+                         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        senders.len(),
+                        answer.len(),
+                        answer
+                    )
+                }
+                (Some("POST"), Some("/whep")) => {
+                    "HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".into()
+                }
+                (Some("DELETE"), Some(path)) => {
+                    deleted_paths.lock().unwrap().push(path.to_string());
+                    "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".into()
+                }
+                _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".into(),
+            };
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (url, deleted)
+}
+
+/// The Whep source of a Video1 (webrtcbin, a decodebin per stream and the sinks of the screen)
+fn whep_video(url: &str, token: Option<&str>) -> VideoTypeSource {
+    VideoTypeSource::new(VideoType::Whep { url: url.into(), token: token.map(|token| token.to_string()) })
+}
+
+#[test]
+fn whep_loopback() {
+    gstreamer::init().unwrap();
+    let (url, deleted) = serve();
+    let (driver, status) = receive_frames(whep_video(&format!("{url}/whep"), Some(TOKEN)), 10);
+
+    assert!(status.frames > 0, "No frame received from the WHEP endpoint : {:?}", status);
+    assert_eq!(status.video_size, Some((WIDTH as u32, HEIGHT as u32)));
+
+    // closing the screen deletes the resource
+    drop(driver);
+    let start = Instant::now();
+    while deleted.lock().unwrap().is_empty() && start.elapsed() < Duration::from_secs(5) {
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(*deleted.lock().unwrap(), vec!["/whep/resource/1".to_string()]);
+}
+
+#[test]
+fn whep_unauthorized() {
+    gstreamer::init().unwrap();
+    let (url, deleted) = serve();
+    let (_driver, status) = receive_frames(whep_video(&format!("{url}/whep"), Some("wrong token")), 1);
+
+    assert_eq!(status.state, StreamState::Reconnecting, "A refused offer is not an error : {:?}", status);
+    let error = status.error.unwrap_or_default();
+    assert!(error.contains("WHEP negotiation failed"), "{}", error);
+    assert_eq!(status.frames, 0);
+    assert!(deleted.lock().unwrap().is_empty(), "No resource has been created");
+}